@@ -2,6 +2,9 @@ use super::*;
 use std::time::{Duration, Instant};
 extern crate num_cpus;
 
+// stop refining after this many samples per pixel.
+const MAX_SAMPLES: u32 = 1024;
+
 pub struct Bakery {
 	scene: Scene,
 	cam_fov: f64,
@@ -10,6 +13,7 @@ pub struct Bakery {
 	dimensions: (u32, u32),
 	prev_mouse: Option<(i32, i32)>,
 	last_wall: Duration,
+	acc: Accumulator,
 }
 
 impl Bakery {
@@ -22,6 +26,7 @@ impl Bakery {
 			last_wall: Duration::from_secs(0),
 			cam_dir: (0.0, 0.0),
 			prev_mouse: None,
+			acc: Accumulator::new(dim).target_samples(MAX_SAMPLES),
 		}
 	}
 
@@ -29,12 +34,27 @@ impl Bakery {
 	pub fn handle_draw(&mut self, (_w, _h): (u32, u32)) -> Image<Color> {
 		let start = Instant::now();
 
-		let img = render(&self.scene, &self.view(), num_threads());
+		let view = self.view();
+		if self.is_refining() {
+			self.acc.add_samples(&self.scene, &view, 1, num_threads());
+		}
+		let img = self.acc.mean();
 
 		self.last_wall = start.elapsed();
 		img
 	}
 
+	/// Whether the image would improve by calling `handle_draw` again
+	/// (i.e. the camera has not moved since, but more samples are needed).
+	pub fn is_refining(&self) -> bool {
+		self.acc.num_samples() < MAX_SAMPLES
+	}
+
+	// discard the accumulated image after the camera moved.
+	fn restart(&mut self) {
+		self.acc = Accumulator::new(self.dimensions).target_samples(MAX_SAMPLES);
+	}
+
 	fn view(&self) -> View {
 		View {
 			camera: self.camera(),
@@ -66,6 +86,7 @@ impl Bakery {
 				self.cam_dir.1 += (dy as f64) * sens;
 				self.cam_dir.1 = clamp(self.cam_dir.1, -PI / 2., PI / 2.);
 				self.prev_mouse = Some((x, y));
+				self.restart();
 			}
 		}
 	}
@@ -101,14 +122,20 @@ impl Bakery {
 			Key::ZoomIn => -5.0 * DEG,
 			_ => 0.0,
 		};
-		self.cam_fov = clamp(self.cam_fov + zoom, 5.0 * DEG, 175.0 * DEG);
+		if zoom != 0.0 {
+			self.cam_fov = clamp(self.cam_fov + zoom, 5.0 * DEG, 175.0 * DEG);
+			self.restart();
+		}
 	}
 
 	/// Move camera relative to its own frame (look dir).
 	/// E.g.: moving along (0, 0, 1) moves along the camea's view direction
 	/// (not absolute Z axis, unless the camera has not been rotated).
 	fn move_cam(&mut self, dir: Vector) {
-		self.cam_pos += self.cam_rotation() * dir
+		if dir != Vector::default() {
+			self.cam_pos += self.cam_rotation() * dir;
+			self.restart();
+		}
 	}
 
 	pub fn key_up(&mut self, _k: Key) {}
@@ -128,7 +155,11 @@ impl Bakery {
 			self.cam_fov / DEG
 		);
 
-		println!("render: {:.1} ms", 1000.0 * self.last_wall.as_secs_f64(),);
+		println!(
+			"render: {:.1} ms, {} samples/pixel",
+			1000.0 * self.last_wall.as_secs_f64(),
+			self.acc.num_samples()
+		);
 	}
 }
 
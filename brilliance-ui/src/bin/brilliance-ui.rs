@@ -4,7 +4,6 @@ use sdl2::event::Event;
 use sdl2::mouse;
 use sdl2::pixels::PixelFormatEnum;
 use sdl2::rect;
-use std::iter;
 
 fn main() -> Result<()> {
 	let (s, v) = spec_from_cli()?;
@@ -27,9 +26,15 @@ fn main() -> Result<()> {
 		//println!("present");
 		canvas.present();
 
-		// wait for at least one event,
+		// while refining, handle all pending events (if any) and keep rendering.
+		// otherwise, wait for at least one event,
 		// handle it and all pending events, if any.
-		for event in iter::once(event_pump.wait_event()).chain(event_pump.poll_iter()) {
+		let first = if bakery.is_refining() {
+			event_pump.poll_event()
+		} else {
+			Some(event_pump.wait_event())
+		};
+		for event in first.into_iter().chain(event_pump.poll_iter()) {
 			match event {
 				Event::Quit { .. } => break 'mainloop,
 				event => handle_event(&mut bakery, event),
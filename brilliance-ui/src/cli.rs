@@ -16,6 +16,14 @@ pub fn spec_from_cli() -> Result<(Scene, View)> {
 
 	let fname = args[0].to_string_lossy();
 
+	// a complete scene description, see `brilliance::scene_file`.
+	if fname.ends_with(".scene") {
+		return match scene_file::load(&fname) {
+			Err(e) => exit(&e.to_string()),
+			Ok(spec) => Ok(spec),
+		};
+	}
+
 	let obj = match parse_file(&fname) {
 		Err(e) => exit(&format!("parsing {}: {}", &fname, e)),
 		Ok(obj) => obj,
@@ -26,9 +34,11 @@ pub fn spec_from_cli() -> Result<(Scene, View)> {
 		max_recursion_depth: 3,
 		ambient: Color::new(0.1, 0.1, 0.1),
 		background: DynMaterial::new(Flat::new(Color::new(0.1, 0.1, 0.1))),
-		objects: vec![DynObj::new(obj)],
+		medium: None,
+		objects: QTree::new(vec![DynObj::new(obj)]),
 		lights: vec![
-			DynLight::new(PointLight::new(Point(1.0, 1.5, 1.5), Color::WHITE.ev(5.0))),
+			// power for an irradiance of about 2^5 at the model, 2.3 m away (inverse square falloff).
+			DynLight::new(PointLight::new(Point(1.0, 1.5, 1.5), Color::WHITE.ev(11.0))),
 			//DynLight::new(PointLight::new(Point(-1.0, 1.0, 1.0), Color::WHITE.ev(3.0))),
 		],
 	};
@@ -38,6 +38,142 @@ fn boundingbox4_intersects_fast(c: &mut Criterion) {
 	});
 }
 
+// A sinc-shaped height field, as used by the golden tests.
+fn sinc_faces(n: u32) -> Vec<Face> {
+	parametric_faces((n, n), |u, v| {
+		let x = u - 0.5;
+		let y = v - 0.5;
+		let r = f64::sqrt(x * x + y * y) * 15.0;
+		let z = 0.5 * if r == 0.0 { 1.0 } else { f64::sin(r) / r };
+		Point(-x, z, y)
+	})
+}
+
+// A bumpy sphere in Wavefront OBJ format, with widely varying triangle sizes
+// (small near the poles, large near the equator).
+fn bumpy_sphere_obj(nu: u32, nv: u32) -> String {
+	let mut obj = String::new();
+	for iv in 0..=nv {
+		for iu in 0..nu {
+			let theta = PI * (iv as f64) / (nv as f64);
+			let phi = 2.0 * PI * (iu as f64) / (nu as f64);
+			let r = 1.0 + 0.1 * f64::sin(7.0 * phi) * f64::sin(5.0 * theta);
+			obj += &format!("v {} {} {}\n", r * theta.sin() * phi.cos(), r * theta.cos(), r * theta.sin() * phi.sin());
+		}
+	}
+	let vert = |iu: u32, iv: u32| iv * nu + (iu % nu) + 1;
+	for iv in 0..nv {
+		for iu in 0..nu {
+			obj += &format!("f {} {} {}\n", vert(iu, iv), vert(iu + 1, iv), vert(iu + 1, iv + 1));
+			obj += &format!("f {} {} {}\n", vert(iu, iv), vert(iu + 1, iv + 1), vert(iu, iv + 1));
+		}
+	}
+	obj
+}
+
+// Rays from a fixed point towards a grid of targets covering the mesh.
+fn bench_rays(obj: &dyn Object) -> Vec<Ray> {
+	let bb = obj.bounds();
+	let start = Point(0.3, 1.5, 2.5);
+	let n = 32;
+	let mut rays = Vec::new();
+	for i in 0..n {
+		for j in 0..n {
+			let (u, v) = ((i as f32 + 0.5) / (n as f32), (j as f32 + 0.5) / (n as f32));
+			let target = Point::from(bb.min + (bb.max - bb.min) * Pointf(u, 0.5, v));
+			rays.push(Ray::new(start, (target - start).normalized()));
+		}
+	}
+	rays
+}
+
+fn bench_intersect(c: &mut Criterion, name: &str, obj: &dyn Object) {
+	let rays = bench_rays(obj);
+	let mat = Flat::new(BLACK);
+	c.bench_function(name, |b| {
+		b.iter(|| {
+			for r in &rays {
+				let mut h = HitRecord::background(&mat, r.dir.into());
+				black_box(obj).intersect(r, &mut h);
+				black_box(h.t());
+			}
+		})
+	});
+}
+
+fn bench_occluded(c: &mut Criterion, name: &str, obj: &dyn Object) {
+	let rays = bench_rays(obj);
+	c.bench_function(name, |b| {
+		b.iter(|| {
+			for r in &rays {
+				black_box(black_box(obj).occluded(r, INF));
+			}
+		})
+	});
+}
+
+fn qtree_sinc(c: &mut Criterion) {
+	let median = QTree::with_options(sinc_faces(256), &BuildOptions::default()).paint(matte(WHITE));
+	let sah = QTree::with_options(sinc_faces(256), &BuildOptions::sah()).paint(matte(WHITE));
+	bench_intersect(c, "qtree_sinc_median", &median);
+	bench_intersect(c, "qtree_sinc_sah", &sah);
+	bench_occluded(c, "qtree_sinc_sah_occluded", &sah);
+}
+
+fn qtree_obj(c: &mut Criterion) {
+	let obj = bumpy_sphere_obj(256, 128);
+	let median = parse_with(obj.as_bytes(), ObjOptions::default()).unwrap();
+	let sah = parse_with(
+		obj.as_bytes(),
+		ObjOptions {
+			tree: BuildOptions::sah(),
+			..ObjOptions::default()
+		},
+	)
+	.unwrap();
+	bench_intersect(c, "qtree_obj_median", &median);
+	bench_intersect(c, "qtree_obj_sah", &sah);
+}
+
+// Shadow rays blocked by an opaque mesh, as seen by the Scene.
+fn scene_occlude(c: &mut Criterion) {
+	let obj = bumpy_sphere_obj(256, 128);
+	let mesh = parse_with(
+		obj.as_bytes(),
+		ObjOptions {
+			tree: BuildOptions::sah(),
+			..ObjOptions::default()
+		},
+	)
+	.unwrap();
+	let rays = bench_rays(&mesh);
+	let scene = Scene {
+		objects: QTree::new(vec![DynObj::new(mesh)]),
+		lights: vec![],
+		ambient: BLACK,
+		background: DynMaterial::new(Flat::new(BLACK)),
+		medium: None,
+		max_recursion_depth: 1,
+		max_iter: 1,
+	};
+	c.bench_function("scene_occlude_blocked", |b| {
+		b.iter(|| {
+			for r in &rays {
+				black_box(black_box(&scene).occlude(r, 10.0, WHITE));
+			}
+		})
+	});
+}
+
+fn qtree_build(c: &mut Criterion) {
+	c.bench_function("qtree_build_sinc_median", |b| {
+		b.iter(|| QTree::with_options(sinc_faces(64), &BuildOptions::default()))
+	});
+	c.bench_function("qtree_build_sinc_sah", |b| {
+		b.iter(|| QTree::with_options(sinc_faces(64), &BuildOptions::sah()))
+	});
+}
+
 // fn triangle_intersects_hit(c: &mut Criterion) {
 // 	let t = Triangle::new(Point(1., 2., -1.), Point(3., 2., -1.), Point(3., 4., -1.));
 //
@@ -80,5 +216,13 @@ fn boundingbox4_intersects_fast(c: &mut Criterion) {
 // 	});
 // }
 
-criterion_group!(benches, boundingbox4_intersects, boundingbox4_intersects_fast);
+criterion_group!(
+	benches,
+	boundingbox4_intersects,
+	boundingbox4_intersects_fast,
+	qtree_sinc,
+	qtree_obj,
+	scene_occlude,
+	qtree_build
+);
 criterion_main!(benches);
@@ -4,6 +4,14 @@ use crate::*;
 // 	x,y,z = f(u,v)
 // where u, v are varied from 0 to 1 (inclusive).
 pub fn parametric<F>(mat: DynMaterial, (num_u, num_v): (u32, u32), f: F) -> DynObj
+where
+	F: Fn(f64, f64) -> Point,
+{
+	DynObj::new(QTree::new(parametric_faces((num_u, num_v), f)).paint(mat))
+}
+
+// Like parametric, but returns the triangles instead of a finished mesh.
+pub fn parametric_faces<F>((num_u, num_v): (u32, u32), f: F) -> Vec<Face>
 where
 	F: Fn(f64, f64) -> Point,
 {
@@ -43,5 +51,5 @@ where
 		}
 	}
 
-	DynObj::new(QTree::new(faces).paint(mat))
+	faces
 }
@@ -9,5 +9,7 @@ pub use tracer::*;
 pub mod api;
 pub use api::*;
 
+pub mod scene_file;
+
 pub mod tests;
 pub use tests::*;
@@ -10,6 +10,15 @@ pub fn uniform_disk((u, v): (f64, f64)) -> (f64, f64) {
 	(r * cos(theta), r * sin(theta))
 }
 
+/// UniformTriangle maps a point (u,v) from the unit square to barycentric coordinates
+/// (weights of the 2nd and 3rd vertex) of a point in a triangle, preserving uniformity.
+///
+/// This is used for sampling triangle-shaped light sources.
+pub fn uniform_triangle((u, v): (f64, f64)) -> (f64, f64) {
+	let su = sqrt(u);
+	(su * (1.0 - v), su * v)
+}
+
 /// CosineSphere transforms a point (u,v) from the unit square to a vector
 /// on the heimsphere around the given normal, cosine weighted.
 /// I.e. the resulting vectors are distributed proportionally to the cosine of the angle with the normal,
@@ -24,6 +33,34 @@ pub fn cosine_sphere((u, v): (f64, f64), normal: Vector) -> Vector {
 	make_basis(normal) * Vector(x, y, z)
 }
 
+/// CosinePowerSphere transforms a point (u,v) from the unit square to a vector
+/// on the hemisphere around the given axis, distributed proportionally to
+/// the cosine of the angle with the axis, raised to `exponent`.
+///
+/// This is used for importance sampling Phong-like (glossy) lobes.
+/// Exponent 1 gives the same distribution as `cosine_sphere`.
+pub fn cosine_power_sphere((u, v): (f64, f64), axis: Vector, exponent: f64) -> Vector {
+	let cos_theta = f64::powf(u, 1.0 / (exponent + 1.0));
+	let sin_theta = sqrt(1.0 - cos_theta * cos_theta);
+	let phi = (2.0 * PI) * v;
+	make_basis(axis) * Vector(sin_theta * cos(phi), sin_theta * sin(phi), cos_theta)
+}
+
+/// UniformCone transforms a point (u,v) from the unit square to a vector
+/// inside the cone around the given axis, with given half-angle, preserving uniformity
+/// (i.e. uniformly distributed over the solid angle).
+///
+/// This is used for sampling light sources that subtend a small angle, like the sun.
+pub fn uniform_cone((u, v): (f64, f64), axis: Vector, half_angle: f64) -> Vector {
+	// 1 - cos, computed accurately for small angles.
+	let s = sin(half_angle / 2.0);
+	let one_minus_cos = u * 2.0 * s * s;
+	let cos_theta = 1.0 - one_minus_cos;
+	let sin_theta = sqrt(f64::max(0.0, one_minus_cos * (2.0 - one_minus_cos)));
+	let phi = (2.0 * PI) * v;
+	make_basis(axis) * Vector(sin_theta * cos(phi), sin_theta * sin(phi), cos_theta)
+}
+
 /// Create orthonormal basis with given z-axis.
 /// See Shirley, Fundamentals of Computer Graphics
 pub fn make_basis(ez: Vector) -> Matrix<f64> {
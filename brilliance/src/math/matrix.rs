@@ -56,6 +56,21 @@ where
 	}
 }
 
+impl<T: Copy> Matrix<T> {
+	/// Transposed matrix.
+	///
+	///     use brilliance::*;
+	///     let m = Matrix::from([[1,2,3],[4,5,6],[7,8,9]]);
+	///     assert_eq!(
+	///         m.transpose(),
+	///         Matrix::from([[1,4,7],[2,5,8],[3,6,9]]),
+	///     );
+	pub fn transpose(&self) -> Self {
+		let m = self;
+		Matrix::from([[m[0][0], m[1][0], m[2][0]], [m[0][1], m[1][1], m[2][1]], [m[0][2], m[1][2], m[2][2]]])
+	}
+}
+
 impl<T> Index<usize> for Matrix<T> {
 	type Output = Vec3<T>;
 
@@ -2,6 +2,7 @@ use super::*;
 
 // 3D Affine transformation.
 //	y = A x + b
+#[derive(Clone, Debug)]
 pub struct Transform {
 	pub a: Matrix<f64>,
 	pub b: Vec3<f64>,
@@ -60,6 +61,12 @@ impl Transform {
 		Self::with_origin(origin, Matrix::unit() * s)
 	}
 
+	/// A transform that scales by (possibly different) factors along x, y and z,
+	/// with `origin` as the fixed point.
+	pub fn scale_xyz(origin: Point, s: Vector) -> Self {
+		Self::with_origin(origin, Matrix::from([[s[0], 0.0, 0.0], [0.0, s[1], 0.0], [0.0, 0.0, s[2]]]))
+	}
+
 	pub fn with_origin(origin: Point, a: Matrix<f64>) -> Self {
 		// (m * (x-o)) + o
 		// m*x - m*o + o
@@ -53,9 +53,16 @@ impl<T> Vec3<T>
 where
 	T: PartialOrd,
 {
+	/// Index of the largest component.
+	///
+	///     use brilliance::*;
+	///     assert_eq!(Vector(1., 2., 3.).argmax(), 2);
+	///     assert_eq!(Vector(1., 3., 2.).argmax(), 1);
+	///     assert_eq!(Vector(3., 2., 1.).argmax(), 0);
+	///
 	pub fn argmax(&self) -> usize {
 		let mut arg = 0;
-		for i in 1..2 {
+		for i in 1..3 {
 			if self[i] > self[arg] {
 				arg = i
 			}
@@ -0,0 +1,629 @@
+//! Declarative scene description files.
+//!
+//! A scene file describes objects, materials, textures, lights, the camera
+//! and render settings, one command per line. E.g.:
+//!
+//! ```text
+//! # render settings
+//! size 960 540
+//! samples 4
+//! recursion 3
+//! ambient 0.1 0.1 0.1
+//! background 0.1 0.1 0.1
+//!
+//! # fog filling the scene: absorption, scattering (per unit length), [asymmetry].
+//! fog 0.01 0.01 0.01  0.05 0.05 0.05  0.7
+//!
+//! # camera: field of view (degrees), position, look-at point.
+//! camera 60
+//! camera_pos 0 1 2
+//! camera_look_at 0 0 0
+//!
+//! # textures can be loaded from image files.
+//! texture earth earth.jpg
+//!
+//! # material <name> <type> <texture or r g b> [args]
+//! material white matte 0.8 0.8 0.8
+//! material globe matte earth
+//! material plastic shiny 0.8 0.2 0.2 0.3
+//! material glass dielectric 1.5
+//! material varnish specular 1 1 1 200
+//! material brass conductor gold 0.3 0.3 0.3
+//! material rusty mix white brass 0.2 0.2 0.2
+//! material neon emissive 1 0.2 0.5
+//! material wax volume 0.1 0.3 0.5  20 20 20
+//!
+//! # objects
+//! sphere 0 0 0 1 globe
+//! obj teapot.obj
+//!
+//! # lights: position, [diameter,] color, exposure value.
+//! # they fall off with the inverse square distance, sphere lights cast soft shadows.
+//! point_light 1 1 1  1 1 1  5
+//! sphere_light 0 3 0  0.5  1 1 1  8
+//!
+//! # light colors may be followed by a physical amount (lengths in meters), then the exposure value:
+//! # lumen (lm), candela (cd) or watt (W) for point, sphere and spot lights,
+//! # lux (lx) for sunlight, nit for area lights.
+//! point_light 0 2.5 0  1 0.9 0.7  800lm  -6
+//!
+//! # one-sided area lights: position, size, look-at point, radiance color, exposure value.
+//! rect_light 0 2 2  1 0.5  0 0 0  1 1 1  4
+//! disk_light 0 3 0  0.5  0 0 0  1 1 1  5
+//!
+//! # sunlight: direction towards the sun, angular diameter, color, exposure value.
+//! directional_light 1 2 1  0.53  1 1 1
+//!
+//! # spot light: position, look-at point, inner and outer cone angle, color, exposure value,
+//! # optionally followed by an IES photometric file shaping the beam.
+//! spot_light 0 3 0  0 0 0  20 30  1 1 1  10
+//! spot_light 2 3 0  2 0 0  90 180  1 1 1  10  downlight.ies
+//!
+//! # environment light: equirectangular sky or outdoor photo, hides the background.
+//! env_light sky.jpg
+//!
+//! # emissive OBJ mesh: file, radiance color, exposure value.
+//! mesh_light panel.obj  1 1 1  2
+//! ```
+//!
+//! Lines starting with `#` are comments. Angles are in degrees.
+//! File names are relative to the directory of the scene file.
+use crate::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Load a scene file. See the module documentation for the format.
+pub fn load(fname: &str) -> Result<(Scene, View)> {
+	let dir = Path::new(fname).parent().unwrap_or(Path::new(""));
+	match File::open(fname) {
+		Err(e) => error(format!("load {}: {}", fname, e)),
+		Ok(f) => match parse(f, dir) {
+			Err(e) => error(format!("{}: {}", fname, e)),
+			Ok(spec) => Ok(spec),
+		},
+	}
+}
+
+/// Like `load`, but accepts a reader.
+/// File names inside the scene are resolved relative to `dir`.
+pub fn parse<R: Read>(r: R, dir: &Path) -> Result<(Scene, View)> {
+	Parser::new(dir).parse(r)
+}
+
+struct Parser {
+	curr_line: u32, // current line, for error messages
+	dir: PathBuf,   // directory for resolving relative file names
+
+	textures: HashMap<String, Arc<dyn Texture>>,
+	materials: HashMap<String, Arc<dyn Material>>,
+
+	objects: Vec<DynObj>,
+	lights: Vec<DynLight>,
+
+	size: (u32, u32),
+	samples: u32,
+	recursion: u32,
+	ambient: Color,
+	background: Option<Arc<dyn Texture>>,
+	fog: Option<Arc<dyn Medium>>,
+
+	fov: f64,
+	camera_pos: Point,
+	camera_look_at: Option<Point>,
+	camera_yaw_pitch: (f64, f64),
+}
+
+impl Parser {
+	fn new(dir: &Path) -> Self {
+		Self {
+			curr_line: 0,
+			dir: dir.to_owned(),
+
+			textures: HashMap::new(),
+			materials: HashMap::new(),
+
+			objects: Vec::new(),
+			lights: Vec::new(),
+
+			size: (960, 540),
+			samples: 1,
+			recursion: 3,
+			ambient: Color::BLACK,
+			background: None,
+			fog: None,
+
+			fov: 60.0,
+			camera_pos: Point(0.0, 0.0, 0.0),
+			camera_look_at: None,
+			camera_yaw_pitch: (0.0, 0.0),
+		}
+	}
+
+	fn parse<R: Read>(mut self, r: R) -> Result<(Scene, View)> {
+		let reader = BufReader::new(r);
+		for line in reader.lines() {
+			// on parse error, prefix with current line number
+			if let Err(e) = self.parse_line(line?) {
+				return error(format!("line {}: {}", self.curr_line, e));
+			}
+		}
+		Ok(self.build())
+	}
+
+	fn build(self) -> (Scene, View) {
+		let background = match self.background {
+			None => DynMaterial::new(Flat::new(Color::BLACK)),
+			Some(tex) => DynMaterial::new(Flat::new(UVMapped::sphere(tex))),
+		};
+
+		let scene = Scene {
+			max_iter: self.samples,
+			max_recursion_depth: self.recursion,
+			ambient: self.ambient,
+			background,
+			medium: self.fog,
+			objects: QTree::new(self.objects),
+			lights: self.lights,
+		};
+
+		let camera = Camera::pinhole(self.fov * DEG).at(self.camera_pos);
+		let camera = match self.camera_look_at {
+			Some(target) => camera.look_at(target),
+			None => camera.yaw_pitch(self.camera_yaw_pitch.0 * DEG, self.camera_yaw_pitch.1 * DEG),
+		};
+
+		let view = View {
+			camera,
+			width: self.size.0,
+			height: self.size.1,
+		};
+
+		(scene, view)
+	}
+
+	fn parse_line(&mut self, line: String) -> Result<()> {
+		self.curr_line += 1;
+		let line = match line.find('#') {
+			Some(i) => &line[..i],
+			None => &line,
+		};
+		let mut fields = line.split_ascii_whitespace();
+		let first = fields.next().unwrap_or_default();
+		let mut args = Args::new(fields.collect());
+
+		match first {
+			"" => return Ok(()), // empty line or comment
+			"size" => self.size = (args.u32()?, args.u32()?),
+			"samples" => self.samples = args.u32()?,
+			"recursion" => self.recursion = args.u32()?,
+			"ambient" => self.ambient = args.color()?,
+			"background" => self.background = Some(self.texture_arg(&mut args)?),
+			"fog" => self.fog = Some(Arc::new(args.medium()?)),
+			"camera" => self.fov = args.f64()?,
+			"camera_pos" => self.camera_pos = args.point()?,
+			"camera_look_at" => self.camera_look_at = Some(args.point()?),
+			"camera_yaw_pitch" => self.camera_yaw_pitch = (args.f64()?, args.f64()?),
+			"texture" => self.parse_texture(&mut args)?,
+			"material" => self.parse_material(&mut args)?,
+			"sphere" => self.parse_sphere(&mut args)?,
+			"obj" => self.parse_obj(&mut args)?,
+			"point_light" => self.parse_point_light(&mut args)?,
+			"sphere_light" => self.parse_sphere_light(&mut args)?,
+			"rect_light" => self.parse_rect_light(&mut args)?,
+			"disk_light" => self.parse_disk_light(&mut args)?,
+			"mesh_light" => self.parse_mesh_light(&mut args)?,
+			"env_light" => self.parse_env_light(&mut args)?,
+			"directional_light" => self.parse_directional_light(&mut args)?,
+			"spot_light" => self.parse_spot_light(&mut args)?,
+			_ => return error(format!("unknown command: {}", first)),
+		}
+
+		args.done()
+	}
+
+	// Named texture, loaded from an image file.
+	//   texture <name> <file>
+	fn parse_texture(&mut self, args: &mut Args) -> Result<()> {
+		let name = args.word()?;
+		let fname = self.path(args.word()?);
+		let img = imageio::load(&fname)?;
+		self.textures.insert(name.to_owned(), Arc::new(MipMap::new(img)));
+		Ok(())
+	}
+
+	// Named material.
+	//   material <name> matte <texture>
+	//   material <name> flat <texture>
+	//   material <name> reflective <r g b>
+	//   material <name> shiny <texture> <shine>
+	//   material <name> specular <texture> <exponent>
+	//   material <name> dielectric <index of refraction>
+	//   material <name> conductor <aluminium|copper|gold|silver> <roughness texture>
+	//   material <name> mix <material> <material> <mask texture>
+	//   material <name> emissive <texture>
+	//   material <name> volume <absorption r g b> <scattering r g b> [asymmetry]
+	fn parse_material(&mut self, args: &mut Args) -> Result<()> {
+		let name = args.word()?;
+		let typ = args.word()?;
+		let mat: Arc<dyn Material> = match typ {
+			"matte" => Arc::new(Matte::new(self.texture_arg(args)?)),
+			"flat" => Arc::new(Flat::new(self.texture_arg(args)?)),
+			"emissive" => Arc::new(Emissive::new(self.texture_arg(args)?)),
+			"reflective" => Arc::new(Reflective(args.color()?)),
+			"volume" => Arc::new(Volume::new(Arc::new(args.medium()?))),
+			"shiny" => {
+				let base = self.texture_arg(args)?;
+				Arc::new(shiny(base, args.f32()?))
+			}
+			"specular" => {
+				let tex = self.texture_arg(args)?;
+				Arc::new(Specular::new(tex, args.f32()?))
+			}
+			"dielectric" => Arc::new(Dielectric::new(args.f64()?)),
+			"conductor" => {
+				let ior = match args.word()? {
+					"aluminium" => ComplexIor::ALUMINIUM,
+					"copper" => ComplexIor::COPPER,
+					"gold" => ComplexIor::GOLD,
+					"silver" => ComplexIor::SILVER,
+					metal => return error(format!("unknown metal: {}", metal)),
+				};
+				Arc::new(Conductor::new(ior, self.texture_arg(args)?))
+			}
+			"mix" => {
+				let a = self.material_arg(args)?;
+				let b = self.material_arg(args)?;
+				Arc::new(Mix::new(a, b, self.texture_arg(args)?))
+			}
+			_ => return error(format!("unknown material type: {}", typ)),
+		};
+		self.materials.insert(name.to_owned(), mat);
+		Ok(())
+	}
+
+	// Sphere with center, diameter and material name.
+	//   sphere <x y z> <diameter> <material>
+	fn parse_sphere(&mut self, args: &mut Args) -> Result<()> {
+		let center = args.point()?;
+		let diam = args.f64()?;
+		let mat = self.material_arg(args)?;
+		self.objects.push(DynObj::new(Sphere::new(center, diam).paint(mat)));
+		Ok(())
+	}
+
+	// Wavefront OBJ file.
+	//   obj <file>
+	fn parse_obj(&mut self, args: &mut Args) -> Result<()> {
+		let fname = self.path(args.word()?);
+		self.objects.push(DynObj::new(parse_file(&fname)?));
+		Ok(())
+	}
+
+	//   point_light <x y z> <r g b> [ev]
+	fn parse_point_light(&mut self, args: &mut Args) -> Result<()> {
+		let pos = args.point()?;
+		let intens = args.intensity()?;
+		self.lights.push(point_light(pos, intens));
+		Ok(())
+	}
+
+	//   sphere_light <x y z> <diameter> <r g b> [ev]
+	fn parse_sphere_light(&mut self, args: &mut Args) -> Result<()> {
+		let pos = args.point()?;
+		let diam = args.f64()?;
+		let intens = args.intensity()?;
+		self.lights.push(sphere_light(pos, diam, intens));
+		Ok(())
+	}
+
+	//   rect_light <x y z> <width> <height> <look_at x y z> <r g b> [ev]
+	fn parse_rect_light(&mut self, args: &mut Args) -> Result<()> {
+		let pos = args.point()?;
+		let size = (args.f64()?, args.f64()?);
+		let look_at = args.point()?;
+		let radiance = args.intensity()?;
+		self.lights.push(rect_light(pos, size, look_at, radiance));
+		Ok(())
+	}
+
+	//   disk_light <x y z> <diameter> <look_at x y z> <r g b> [ev]
+	fn parse_disk_light(&mut self, args: &mut Args) -> Result<()> {
+		let pos = args.point()?;
+		let diam = args.f64()?;
+		let look_at = args.point()?;
+		let radiance = args.intensity()?;
+		self.lights.push(disk_light(pos, diam, look_at, radiance));
+		Ok(())
+	}
+
+	// Wavefront OBJ file, emitting light from all faces.
+	//   mesh_light <file> <r g b> [ev]
+	fn parse_mesh_light(&mut self, args: &mut Args) -> Result<()> {
+		let fname = self.path(args.word()?);
+		let radiance = args.intensity()?;
+		self.lights.push(mesh_light(parse_file_faces(&fname, ObjOptions::default())?, radiance));
+		Ok(())
+	}
+
+	// Equirectangular image surrounding the scene.
+	//   env_light <file>
+	fn parse_env_light(&mut self, args: &mut Args) -> Result<()> {
+		let fname = self.path(args.word()?);
+		self.lights.push(env_light(imageio::load(&fname)?));
+		Ok(())
+	}
+
+	//   directional_light <dx dy dz> <angular diameter> <r g b> [ev]
+	fn parse_directional_light(&mut self, args: &mut Args) -> Result<()> {
+		let dir = args.vector()?;
+		let diam = args.f64()? * DEG;
+		let intens = args.intensity()?;
+		self.lights.push(directional_light(dir, diam, intens));
+		Ok(())
+	}
+
+	//   spot_light <x y z> <look_at x y z> <inner angle> <outer angle> <r g b> [ev] [ies file]
+	fn parse_spot_light(&mut self, args: &mut Args) -> Result<()> {
+		let pos = args.point()?;
+		let look_at = args.point()?;
+		let (inner, outer) = (args.f64()? * DEG, args.f64()? * DEG);
+		let power = args.intensity()?;
+		let mut spot = SpotLight::new(pos, power).look_at(look_at).with_cone(inner, outer);
+		if args.has_more() {
+			let fname = self.path(args.word()?);
+			match parse_ies_file(Path::new(&fname)) {
+				Err(e) => return error(format!("{}: {}", fname, e)),
+				Ok(ies) => spot = spot.with_ies(ies),
+			}
+		}
+		self.lights.push(DynLight::new(spot));
+		Ok(())
+	}
+
+	// A texture argument: either a solid color (`r g b`) or the name of a texture.
+	fn texture_arg(&self, args: &mut Args) -> Result<Arc<dyn Texture>> {
+		if args.peek_number() {
+			return Ok(Arc::new(args.color()?));
+		}
+		let name = args.word()?;
+		match self.textures.get(name) {
+			Some(tex) => Ok(tex.clone()),
+			None => error(format!("undefined texture: {}", name)),
+		}
+	}
+
+	fn material_arg(&self, args: &mut Args) -> Result<Arc<dyn Material>> {
+		let name = args.word()?;
+		match self.materials.get(name) {
+			Some(mat) => Ok(mat.clone()),
+			None => error(format!("undefined material: {}", name)),
+		}
+	}
+
+	// Resolve a file name relative to the scene file's directory.
+	fn path(&self, fname: &str) -> String {
+		self.dir.join(fname).to_string_lossy().into_owned()
+	}
+}
+
+// Arguments following a command, consumed left-to-right.
+struct Args<'a> {
+	args: Vec<&'a str>,
+	next: usize,
+}
+
+impl<'a> Args<'a> {
+	fn new(args: Vec<&'a str>) -> Self {
+		Self { args, next: 0 }
+	}
+
+	fn word(&mut self) -> Result<&'a str> {
+		match self.args.get(self.next) {
+			None => error(format!("need more than {} arguments", self.next)),
+			Some(arg) => {
+				self.next += 1;
+				Ok(arg)
+			}
+		}
+	}
+
+	fn f64(&mut self) -> Result<f64> {
+		let arg = self.word()?;
+		match arg.parse() {
+			Ok(v) => Ok(v),
+			Err(_) => error(format!("argument {}: need a number, got: {}", self.next, arg)),
+		}
+	}
+
+	fn f32(&mut self) -> Result<f32> {
+		Ok(self.f64()? as f32)
+	}
+
+	fn u32(&mut self) -> Result<u32> {
+		let arg = self.word()?;
+		match arg.parse() {
+			Ok(v) => Ok(v),
+			Err(_) => error(format!("argument {}: need a positive integer, got: {}", self.next, arg)),
+		}
+	}
+
+	fn point(&mut self) -> Result<Point> {
+		Ok(Point(self.f64()?, self.f64()?, self.f64()?))
+	}
+
+	fn vector(&mut self) -> Result<Vector> {
+		Ok(Vector(self.f64()?, self.f64()?, self.f64()?))
+	}
+
+	fn color(&mut self) -> Result<Color> {
+		let (r, g, b) = (self.f32()?, self.f32()?, self.f32()?);
+		if r < 0.0 || g < 0.0 || b < 0.0 {
+			return error(format!("color must not be negative, got: {} {} {}", r, g, b));
+		}
+		Ok(Color::new(r, g, b))
+	}
+
+	// A color, optionally followed by an amount with units (e.g. `800lm`),
+	// and/or an exposure value (`Color::ev`).
+	fn intensity(&mut self) -> Result<Color> {
+		let mut c = self.color()?;
+		if let Some(amount) = self.args.get(self.next).and_then(|arg| with_units(c, arg)) {
+			self.next += 1;
+			c = amount;
+		}
+		if self.peek_number() {
+			Ok(c.ev(self.f64()?))
+		} else {
+			Ok(c)
+		}
+	}
+
+	// Homogeneous medium: absorption and scattering color,
+	// optionally followed by the asymmetry of the phase function.
+	fn medium(&mut self) -> Result<HomogeneousMedium> {
+		let m = HomogeneousMedium::new(self.color()?, self.color()?);
+		if !self.peek_number() {
+			return Ok(m);
+		}
+		let g = self.f64()?;
+		if !(g > -1.0 && g < 1.0) {
+			return error(format!("asymmetry must be between -1 and 1, got: {}", g));
+		}
+		Ok(m.with_asymmetry(g))
+	}
+
+	fn has_more(&self) -> bool {
+		self.next < self.args.len()
+	}
+
+	// Is the next argument a number?
+	fn peek_number(&self) -> bool {
+		match self.args.get(self.next) {
+			None => false,
+			Some(arg) => arg.parse::<f64>().is_ok(),
+		}
+	}
+
+	// Check that all arguments have been consumed.
+	fn done(&self) -> Result<()> {
+		if self.next < self.args.len() {
+			error(format!("too many arguments: {:?}", &self.args[self.next..]))
+		} else {
+			Ok(())
+		}
+	}
+}
+
+// Conversion from an amount of light in some units to a color, see `units`.
+type Convert = fn(Color, f64) -> Color;
+
+// An amount of light with units, like `800lm`, in the hue of color `c`. See `units`.
+// None if `arg` is not a number followed by known units.
+fn with_units(c: Color, arg: &str) -> Option<Color> {
+	let units: [(&str, Convert); 7] = [
+		("lm", lumens),
+		("cd", candela),
+		("W", watts),
+		("lx", lux),
+		("lux", lux),
+		("nit", nits),
+		("nits", nits),
+	];
+	for (suffix, convert) in units.iter() {
+		if let Some(Ok(v)) = arg.strip_suffix(suffix).map(str::parse) {
+			return Some(convert(c, v));
+		}
+	}
+	None
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn parse_str(input: &str) -> Result<(Scene, View)> {
+		parse(input.as_bytes(), Path::new(""))
+	}
+
+	#[test]
+	fn test_parse() {
+		let (scene, view) = parse_str(
+			r"
+# test scene
+size 320 240
+samples 2
+recursion 1
+ambient 0.1 0.1 0.1
+
+camera 45
+camera_pos 0 1 4
+camera_look_at 0 0 0
+
+material white matte 0.8 0.8 0.8
+material red shiny 1 0 0 0.5
+material glass dielectric 1.5
+material varnish specular 1 1 1 200
+material brass conductor gold 0.3 0.3 0.3
+material rusty mix white brass 0.2 0.2 0.2
+material neon emissive 1 0.2 0.5
+material wax volume 0.1 0.3 0.5  20 20 20 0.2
+fog 0.01 0.01 0.01  0.05 0.05 0.05
+
+sphere 0 0 0 1 white
+sphere 1 0 0 0.5 red # trailing comment
+
+point_light 1 1 1  1 1 1  5
+sphere_light 0 3 0  0.5  1 1 1
+rect_light 0 2 2  1 0.5  0 0 0  1 1 1  4
+disk_light 0 3 0  0.5  0 0 0  1 1 1
+directional_light 1 2 1  0.53  1 1 1
+spot_light 0 3 0  0 0 0  20 30  1 1 1  10
+point_light 0 2.5 0  1 0.9 0.7  800lm  -6
+rect_light 0 2 2  1 0.5  0 0 0  1 1 1  200nits
+",
+		)
+		.unwrap();
+
+		assert_eq!(view.dimensions(), (320, 240));
+		assert_eq!(scene.max_iter, 2);
+		assert_eq!(scene.max_recursion_depth, 1);
+		assert_eq!(scene.ambient, Color::new(0.1, 0.1, 0.1));
+		assert_eq!(scene.objects.len(), 2);
+		assert_eq!(scene.lights.len(), 8);
+		assert!(scene.medium.is_some());
+	}
+
+	#[test]
+	fn test_units() {
+		let mut args = Args::new(vec!["1", "1", "1", "800lm", "-2"]);
+		assert_eq!(args.intensity().unwrap(), lumens(Color::WHITE, 200.0));
+		let mut args = Args::new(vec!["1", "0", "0", "100cd"]);
+		assert_eq!(args.intensity().unwrap(), candela(Color::RED, 100.0));
+		let mut args = Args::new(vec!["1", "1", "1", "1e5lx"]);
+		assert_eq!(args.intensity().unwrap(), lux(Color::WHITE, 1e5));
+	}
+
+	#[test]
+	fn test_errors() {
+		fn err(input: &str) -> String {
+			match parse_str(input) {
+				Ok(_) => panic!("expected error for {:?}", input),
+				Err(e) => e.to_string(),
+			}
+		}
+
+		assert_eq!(err("\n\nfoo 1 2 3"), "line 3: unknown command: foo");
+		assert_eq!(err("size 1"), "line 1: need more than 1 arguments");
+		assert_eq!(err("size 1 2 3"), "line 1: too many arguments: [\"3\"]");
+		assert_eq!(err("samples x"), "line 1: argument 1: need a positive integer, got: x");
+		assert_eq!(err("sphere 0 0 0 1 white"), "line 1: undefined material: white");
+		assert_eq!(err("material m matte tex"), "line 1: undefined texture: tex");
+		assert_eq!(err("material m mix a b 1 1 1"), "line 1: undefined material: a");
+		assert!(err("spot_light 0 3 0  0 0 0  20 30  1 1 1  10  nonexistent.ies").starts_with("line 1: nonexistent.ies: "));
+		assert_eq!(err("point_light 0 0 0  1 1 1  800lumen"), "line 1: too many arguments: [\"800lumen\"]");
+		assert_eq!(err("fog 0 0 0  1 1 1  1"), "line 1: asymmetry must be between -1 and 1, got: 1");
+	}
+}
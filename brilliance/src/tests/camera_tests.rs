@@ -6,12 +6,12 @@ fn look_at() {
 	test(
 		"camera_look_at",
 		&Scene {
-			objects: vec![
+			objects: QTree::new(vec![
 				DynObj::new(Sphere::new(Point(0., 0., 0.), diam).paint(flat(WHITE))),
 				DynObj::new(Sphere::new(Point(1., 0., 0.), diam).paint(flat(RED))),
 				DynObj::new(Sphere::new(Point(0., 1., 0.), diam).paint(flat(GREEN))),
 				DynObj::new(Sphere::new(Point(0., 0., 1.), diam).paint(flat(BLUE))),
-			],
+			]),
 			..default_scene()
 		},
 		&View {
@@ -29,11 +29,11 @@ fn look_dir() {
 	test(
 		"camera_look_dir",
 		&Scene {
-			objects: vec![
+			objects: QTree::new(vec![
 				DynObj::new(Sphere::new(Point(1., 0., 0.), diam).paint(flat(RED))),
 				DynObj::new(Sphere::new(Point(0., 1., 0.), diam).paint(flat(GREEN))),
 				DynObj::new(Sphere::new(Point(0., 0., 1.), diam).paint(flat(BLUE))),
-			],
+			]),
 			..default_scene()
 		},
 		&View {
@@ -52,12 +52,12 @@ fn pinhole_handedness() {
 	test(
 		"camera_pinhole_handedness",
 		&Scene {
-			objects: vec![
+			objects: QTree::new(vec![
 				DynObj::new(Sphere::new(Point(0., 0., 0.), diam).paint(flat(WHITE))),
 				DynObj::new(Sphere::new(Point(1., 0., 0.), diam).paint(flat(RED))),
 				DynObj::new(Sphere::new(Point(0., 1., 0.), diam).paint(flat(GREEN))),
 				DynObj::new(Sphere::new(Point(0., 0., 1.), diam).paint(flat(BLUE))),
-			],
+			]),
 			..default_scene()
 		},
 		&View {
@@ -86,11 +86,11 @@ fn pinhole_fov() {
 	test(
 		"camera_pinhole_fov",
 		&Scene {
-			objects: vec![
+			objects: QTree::new(vec![
 				DynObj::new(Sphere::new(Point(0., 0., 0.), diam).paint(flat(WHITE))),
 				DynObj::new(Sphere::new(Point(1., 0., 0.), diam).paint(flat(RED))),
 				DynObj::new(Sphere::new(Point(-1., 0., 0.), diam).paint(flat(GREEN))),
-			],
+			]),
 			..default_scene()
 		},
 		&View {
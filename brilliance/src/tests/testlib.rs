@@ -24,11 +24,17 @@ pub fn default_scene() -> Scene {
 		max_recursion_depth: 0,
 		ambient: Color::BLACK,
 		background: DynMaterial::new(Flat::new(Color::BLACK)),
-		objects: vec![],
+		medium: None,
+		objects: QTree::new(vec![]),
 		lights: vec![point_light(Point(0.0, 20.0, 0.0), WHITE.ev(10.0))],
 	}
 }
 
+// Random numbers for tests that sample a single pixel, see `TileRng::for_pix`.
+pub fn test_rng() -> TileRng {
+	TileRng::new((1, 1), 1)
+}
+
 pub fn default_view() -> View {
 	View {
 		camera: Camera::pinhole(60.0 * DEG)
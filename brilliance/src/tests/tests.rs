@@ -6,7 +6,7 @@ fn render_sinc() {
 	//test(
 	//	"sinc",
 	//	&Scene {
-	//		objects: vec![floor(4.0, 4.0), sinc(64)],
+	//		objects: QTree::new(vec![floor(4.0, 4.0), sinc(64)]),
 	//		lights: vec![
 	//			point_light(Point(1.0, 1.0, 1.0), WHITE.ev(5.0)),
 	//			point_light(Point(-1.0, 2.0, 1.0), WHITE.ev(5.0)),
@@ -29,7 +29,7 @@ fn render_f32bleed() {
 	test(
 		"f32bleed",
 		&Scene {
-			objects: vec![floor(4.0, 4.0), sinc(64)],
+			objects: QTree::new(vec![floor(4.0, 4.0), sinc(64)]),
 			lights: vec![
 				point_light(Point(1.0, 1.0, 1.0), WHITE.ev(5.0)),
 				point_light(Point(-1.0, 2.0, 1.0), WHITE.ev(5.0)),
@@ -6,7 +6,8 @@ pub fn test_spec(n: u32) -> (Scene, View) {
 		max_iter: 7,
 		ambient: Color::new(0.1, 0.1, 0.1),
 		background: DynMaterial::new(Flat::new(Color::new(0.1, 0.1, 0.1))),
-		objects: vec![sinc(n), floor()],
+		medium: None,
+		objects: QTree::new(vec![sinc(n), floor()]),
 		lights: vec![
 			DynLight::new(PointLight::new(Point(1.0, 1.0, 1.0), Color::WHITE.ev(5.0))),
 			DynLight::new(PointLight::new(Point(-1.0, 2.0, 1.0), Color::WHITE.ev(5.0))),
@@ -0,0 +1,161 @@
+use super::*;
+
+/// Accumulator renders an image progressively:
+/// each call to `add_samples` adds more samples per pixel to the same image,
+/// while `mean` returns the image rendered so far.
+///
+/// ```no_run
+/// # use brilliance::*;
+/// # fn refine(scene: &Scene, view: &View) -> Result<()> {
+/// let mut acc = Accumulator::new(view.dimensions()).target_samples(256);
+/// while acc.num_samples() < 256 {
+///     acc.add_samples(scene, view, 16, 4);
+///     save(&acc.mean(), "progress.png")?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Accumulator {
+	dim: (u32, u32),
+	tiles: Vec<Tile>,
+	num_samples: u32,
+	antialias: bool,
+	target_samples: Option<u32>,
+}
+
+impl Accumulator {
+	/// An empty accumulator for an image of size `(w, h)`.
+	/// Antialiasing is enabled by default.
+	pub fn new((w, h): (u32, u32)) -> Self {
+		Self {
+			dim: (w, h),
+			tiles: Tile::split((w, h)),
+			num_samples: 0,
+			antialias: true,
+			target_samples: None,
+		}
+	}
+
+	/// Enable or disable sub-pixel jittering of camera rays.
+	/// Without antialiasing, a single sample per pixel is usually enough for a preview.
+	pub fn antialias(mut self, antialias: bool) -> Self {
+		self.antialias = antialias;
+		self
+	}
+
+	/// Number of samples per pixel the image is going to be refined to.
+	/// Textures are filtered less as more samples are averaged,
+	/// so that they are sharp, but not aliased, after that many samples.
+	/// By default, the number of samples rendered so far (including the ones being added).
+	pub fn target_samples(mut self, n: u32) -> Self {
+		self.target_samples = Some(n);
+		self
+	}
+
+	/// Render `n` more samples per pixel, using `num_threads` threads.
+	/// `view` must have the same dimensions as the accumulator.
+	pub fn add_samples(&mut self, scene: &Scene, view: &View, n: u32, num_threads: u32) {
+		assert!(
+			view.dimensions() == self.dim,
+			"Accumulator: view dimensions {:?} do not match {:?}",
+			view.dimensions(),
+			self.dim
+		);
+		let iters = self.num_samples..(self.num_samples + n);
+		let samples = self.target_samples.unwrap_or(self.num_samples + n);
+		let spacing = sample_spacing(samples, self.antialias);
+		let tiles = std::mem::take(&mut self.tiles);
+		self.tiles = render_tiles(scene, view, tiles, iters, self.antialias, spacing, num_threads);
+		self.num_samples += n;
+	}
+
+	/// Number of samples per pixel rendered so far.
+	pub fn num_samples(&self) -> u32 {
+		self.num_samples
+	}
+
+	/// Image size.
+	pub fn dimensions(&self) -> (u32, u32) {
+		self.dim
+	}
+
+	/// The mean of all samples rendered so far.
+	/// Black if no samples have been added yet.
+	pub fn mean(&self) -> Image<Color> {
+		let mut img = Image::new(self.dim);
+		if self.num_samples != 0 {
+			for tile in &self.tiles {
+				tile.draw_mean(&mut img, self.num_samples);
+			}
+		}
+		img
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::default_scene;
+
+	fn scene<M: Material + 'static>(mat: M) -> Scene {
+		Scene {
+			objects: QTree::new(vec![DynObj::new(Sphere::new(Point(0., 0., -2.), 1.0).paint(mat))]),
+			max_recursion_depth: 2,
+			..default_scene()
+		}
+	}
+
+	fn view() -> View {
+		View {
+			camera: Camera::pinhole(60.0 * DEG),
+			width: 48,
+			height: 32,
+		}
+	}
+
+	// Render `passes` times `n` samples.
+	fn render(scene: &Scene, passes: u32, n: u32, antialias: bool) -> Image<Color> {
+		let mut acc = Accumulator::new(view().dimensions()).antialias(antialias).target_samples(8);
+		assert_eq!(acc.num_samples(), 0);
+		for i in 1..=passes {
+			acc.add_samples(scene, &view(), n, 2);
+			assert_eq!(acc.num_samples(), i * n);
+		}
+		acc.mean()
+	}
+
+	// Average over all pixels.
+	fn average(img: &Image<Color>) -> Color {
+		let (w, h) = img.dimensions();
+		let mut acc = Color::BLACK;
+		for y in 0..h {
+			for x in 0..w {
+				acc += img.at((x, y));
+			}
+		}
+		acc / (w * h) as f32
+	}
+
+	#[test]
+	fn test_empty() {
+		let acc = Accumulator::new((4, 3));
+		assert_eq!(acc.num_samples(), 0);
+		assert_eq!(acc.dimensions(), (4, 3));
+		assert_eq!(acc.mean(), Image::new((4, 3)));
+	}
+
+	#[test]
+	fn test_passes() {
+		// identical samples: the mean is exact, whatever the number of passes.
+		let flat = scene(Flat::new(Color::new(0.5, 0.5, 0.5)));
+		let (a, b) = (render(&flat, 4, 2, false), render(&flat, 1, 8, false));
+		assert_eq!(a, b);
+		assert_eq!(a.at((24, 16)), Color::new(0.5, 0.5, 0.5));
+
+		// random samples: the same mean, up to noise.
+		let matte = scene(Matte::new(Color::WHITE));
+		let (a, b) = (average(&render(&matte, 4, 2, true)), average(&render(&matte, 1, 8, true)));
+		assert!(b.r() > 0.0);
+		assert!((a.r() - b.r()).abs() < 0.03 * b.r(), "{:?} != {:?}", a, b);
+	}
+}
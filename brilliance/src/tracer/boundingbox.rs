@@ -45,6 +45,17 @@ impl BoundingBox {
 		}
 	}
 
+	/// Surface area.
+	///
+	///     use brilliance::*;
+	///     let bb = BoundingBox::new(Pointf(0., 0., 0.), Pointf(1., 2., 3.));
+	///     assert_eq!(bb.area(), 22.0);
+	///
+	pub fn area(&self) -> f32 {
+		let d = self.max - self.min;
+		2.0 * (d[0] * d[1] + d[1] * d[2] + d[2] * d[0])
+	}
+
 	/// The bounding box's center.
 	pub fn center(&self) -> Pointf {
 		(self.min + self.max) * 0.5
@@ -71,9 +71,18 @@ impl Camera {
 		self.rot(Transform::yaw_pitch(yaw_radians, pitch_radians))
 	}
 
+	/// Ray through camera coordinates `uv` (see `index_to_cam`),
+	/// with differentials per unit of u and v (see `RayDiff`).
 	pub fn ray_from(&self, rng: &mut Rng, uv: (f64, f64)) -> Ray {
 		let orig = self.projection.ray_from(rng, uv);
-		Ray::new(orig.start + self.position, self.rotation * orig.dir)
+		let rot = self.rotation;
+		let diff = orig.diff.expect("projection provides differentials");
+		Ray::new(orig.start + self.position, rot * orig.dir).with_diff(RayDiff {
+			dstart_dx: rot * diff.dstart_dx,
+			dstart_dy: rot * diff.dstart_dy,
+			ddir_dx: rot * diff.ddir_dx,
+			ddir_dy: rot * diff.ddir_dy,
+		})
 	}
 }
 
@@ -86,7 +95,18 @@ impl Projection {
 		let y = v - 0.5;
 
 		match *self {
-			Pinhole { focal_len } => Ray::new(Point(0.0, 0.0, 0.0), Vector(x, y, focal_len).normalized()),
+			Pinhole { focal_len } => {
+				// derivative of the normalized direction w/|w| is (dw - dir (dir.dw)) / |w|.
+				let w = Vector(x, y, focal_len);
+				let dir = w.normalized();
+				let ddir = |dw: Vector| (1.0 / w.len()) * (dw - dir.dot(dw) * dir);
+				Ray::new(Point(0.0, 0.0, 0.0), dir).with_diff(RayDiff {
+					dstart_dx: Vector::ZERO,
+					dstart_dy: Vector::ZERO,
+					ddir_dx: ddir(Vector::EX),
+					ddir_dy: ddir(Vector::EY),
+				})
+			}
 		}
 	}
 }
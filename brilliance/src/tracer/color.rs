@@ -85,6 +85,22 @@ impl Color {
 		self.0.max3()
 	}
 
+	/// Mean of the three components.
+	///
+	///     use brilliance::*;
+	///     assert_eq!(Color::new(1.0, 2.0, 3.0).average(), 2.0);
+	pub fn average(&self) -> f32 {
+		(self.r() + self.g() + self.b()) / 3.0
+	}
+
+	/// Perceived brightness (Rec. 709 weights of the linear components).
+	///
+	///     use brilliance::*;
+	///     assert_eq!(WHITE.luminance(), 1.0);
+	pub fn luminance(&self) -> f32 {
+		0.2126 * self.r() + 0.7152 * self.g() + 0.0722 * self.b()
+	}
+
 	/// Apply f to each component.
 	///
 	///     use brilliance::*;
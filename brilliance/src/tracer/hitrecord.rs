@@ -62,6 +62,16 @@ pub struct HitCoords {
 	pub shading_normalf: Vectorf,
 	// Local U,V coordinates at intersection, chosen by the Object.
 	pub tex_coords: Pointf,
+
+	/// Tangent vectors: derivatives of the position with respect to
+	/// texture coordinates u and v. Used for normal and bump mapping.
+	/// Zero if the Object does not provide them.
+	pub dpdu: Vectorf,
+	pub dpdv: Vectorf,
+
+	/// Footprint of a pixel in texture space, for texture filtering.
+	/// Set by the Scene for camera rays (see `RayDiff`), zero otherwise.
+	pub footprint: Footprint,
 }
 
 impl HitCoords {
@@ -71,6 +81,9 @@ impl HitCoords {
 			geom_normalf: dir,
 			shading_normalf: dir,
 			tex_coords: dir,
+			dpdu: Vectorf::ZERO,
+			dpdv: Vectorf::ZERO,
+			footprint: Footprint::default(),
 		}
 	}
 
@@ -96,6 +109,23 @@ impl HitCoords {
 		self.geom_normalf = geom_norm;
 		self.shading_normalf = shad_norm;
 		self.tex_coords = tex_coords;
+		self.dpdu = Vectorf::ZERO;
+		self.dpdv = Vectorf::ZERO;
+	}
+
+	/// Set the tangent vectors `dpdu`, `dpdv`, given the derivatives of position (`dp1`, `dp2`)
+	/// and of texture coordinates (`dt1`, `dt2`) along two directions on the surface
+	/// (e.g. the edges of a triangle).
+	/// Leaves them zero if the texture coordinates are degenerate.
+	pub fn set_tangents(&mut self, dp1: Vector, dp2: Vector, dt1: (f64, f64), dt2: (f64, f64)) {
+		// invert the 2x2 Jacobian of texture coordinates.
+		let det = dt1.0 * dt2.1 - dt2.0 * dt1.1;
+		if det.abs() < 1e-12 {
+			return;
+		}
+		let inv = 1.0 / det;
+		self.dpdu = ((dt2.1 * dp1 - dt1.1 * dp2) * inv).into();
+		self.dpdv = ((dt1.0 * dp2 - dt2.0 * dp1) * inv).into();
 	}
 
 	/// is_valid is intended for use with debug_assert!
@@ -36,3 +36,42 @@ pub fn load(fname: &str) -> Result<Image<RGB>> {
 	});
 	Ok(img)
 }
+
+/// Load an opacity mask, e.g. for `WithAlpha` or `Dissolve`.
+/// Uses the alpha channel if the image has one, otherwise the luminance.
+/// Values are linear, between 0 (transparent) and 1 (opaque).
+pub fn load_opacity(fname: &str) -> Result<Image<Color>> {
+	let orig = match image::open(fname) {
+		Ok(img) => img,
+		Err(e) => return error(format!("load {}: {}", fname, e)),
+	};
+
+	let img = if orig.color().has_alpha() {
+		let rgba = orig.into_rgba();
+		Image::<Color>::from_fn(rgba.dimensions(), |x, y| gray(rgba.get_pixel(x, y)[3]))
+	} else {
+		let luma = orig.into_luma();
+		Image::<Color>::from_fn(luma.dimensions(), |x, y| gray(luma.get_pixel(x, y)[0]))
+	};
+	Ok(img)
+}
+
+/// Load an image holding data rather than colors, e.g. a normal or height map.
+/// Unlike `load`, values are not sRGB-decoded, but mapped linearly to 0..1.
+pub fn load_linear(fname: &str) -> Result<Image<Color>> {
+	let orig = match image::open(fname) {
+		Ok(img) => img,
+		Err(e) => return error(format!("load {}: {}", fname, e)),
+	};
+	let rgb = orig.into_rgb();
+	let img = Image::<Color>::from_fn(rgb.dimensions(), |x, y| {
+		let pix = rgb.get_pixel(x, y);
+		Color::new(pix[0] as f32 / 255.0, pix[1] as f32 / 255.0, pix[2] as f32 / 255.0)
+	});
+	Ok(img)
+}
+
+fn gray(v: u8) -> Color {
+	let v = (v as f32) / 255.0;
+	Color::new(v, v, v)
+}
@@ -32,6 +32,10 @@ impl Object for DynLight {
 	fn intersect<'s>(&'s self, r: &Ray, h: &mut HitRecord<'s>) {
 		self.inner().intersect(r, h)
 	}
+
+	fn occluded(&self, r: &Ray, max_t: f64) -> Occlusion {
+		self.inner().occluded(r, max_t)
+	}
 }
 
 impl Light for DynLight {
@@ -0,0 +1,100 @@
+use super::*;
+
+/// An infinitely distant light source, casting parallel rays, like the sun.
+///
+/// `dir` points towards the light (e.g. up for the sun at noon).
+/// The intensity does not fall off with distance: it is the brightness
+/// of a white `Matte` surface facing the light.
+///
+/// By default, the light is a point in the sky, casting sharp shadows.
+/// `with_angular_diameter` makes it a disk in the sky, casting soft shadows
+/// (the sun is about 0.53 degrees wide). The light itself is not visible to the camera.
+///
+///     use brilliance::*;
+///     let sun = DirectionalLight::new(Vector(1., 2., 1.), WHITE).with_angular_diameter(0.53 * DEG);
+pub struct DirectionalLight {
+	dir: Vector,
+	half_angle: f64,
+	intensity: Color,
+}
+
+/// Directional light shining from direction `dir`, with given intensity and angular diameter.
+pub fn directional_light(dir: Vector, angular_diam: f64, intensity: Color) -> DynLight {
+	DynLight::new(DirectionalLight::new(dir, intensity).with_angular_diameter(angular_diam))
+}
+
+impl DirectionalLight {
+	pub fn new(dir: Vector, intensity: Color) -> Self {
+		Self {
+			dir: dir.normalized(),
+			half_angle: 0.0,
+			intensity,
+		}
+	}
+
+	/// Angle (in radians) that the light subtends, like the sun's 0.53 degrees.
+	pub fn with_angular_diameter(self, angle: f64) -> Self {
+		Self {
+			half_angle: angle / 2.0,
+			..self
+		}
+	}
+}
+
+impl Bounded for DirectionalLight {
+	fn bounds(&self) -> BoundingBox {
+		BoundingBox::empty(Pointf::default())
+	}
+}
+
+impl Object for DirectionalLight {
+	fn intersect<'s>(&'s self, _: &Ray, _: &mut HitRecord<'s>) {
+		// never intersects
+	}
+
+	fn occluded(&self, _: &Ray, _: f64) -> Occlusion {
+		Occlusion::Clear
+	}
+}
+
+impl Light for DirectionalLight {
+	fn sample(&self, rng: &mut Rng, target: Point) -> (Point, Color) {
+		// uniformly inside the cone that the light subtends.
+		// Its radiance times solid angle stays the intensity, whatever the angle.
+		let dir = if self.half_angle == 0.0 {
+			self.dir
+		} else {
+			uniform_cone((rng.random() as f64, rng.random() as f64), self.dir, self.half_angle)
+		};
+		(target + ENV_DIST * dir, self.intensity)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::test_rng;
+
+	#[test]
+	fn test_sample() {
+		let mut trng = test_rng();
+		let mut rng = trng.for_pix((0, 0), 0);
+
+		// sharp: always the same direction, at any distance.
+		let light = DirectionalLight::new(Vector(0., 2., 0.), Color::WHITE);
+		for &target in &[Point(0., 0., 0.), Point(1000., -5., 3.)] {
+			let (pos, intens) = light.sample(&mut rng, target);
+			assert!(((pos - target).normalized() - Vector::EY).len() < 1e-12);
+			assert_eq!(intens, Color::WHITE);
+		}
+
+		// soft: directions spread over a cone of 10 degrees around the axis.
+		let light = light.with_angular_diameter(10.0 * DEG);
+		let mut max_angle: f64 = 0.0;
+		for _ in 0..1000 {
+			let (pos, _) = light.sample(&mut rng, Point(0., 0., 0.));
+			max_angle = max_angle.max(f64::acos(pos.normalized()[1]));
+		}
+		assert!(max_angle <= 5.0 * DEG + 1e-9 && max_angle > 4.5 * DEG, "{}", max_angle / DEG);
+	}
+}
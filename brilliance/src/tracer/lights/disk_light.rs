@@ -0,0 +1,130 @@
+use super::panel::*;
+use super::*;
+
+/// A disk-shaped, one-sided light source, like a round ceiling lamp or spotlight lens.
+/// It casts soft shadows, and is visible to the camera.
+///
+/// Like `RectLight`, it faces down (-Y) by default, `look_at` or `look_dir` turn it,
+/// and the emitted radiance may be a texture, mapped to the disk's bounding square.
+///
+///     use brilliance::*;
+///     let lamp = DiskLight::new(Point(0., 3., 0.), 0.5, WHITE.ev(5.0));
+pub struct DiskLight<T: Texture> {
+	panel: Panel,
+	radius: f64,
+	emissive: Emissive<T>,
+}
+
+/// Disk light with given center, diameter and radiance, turned to face `look_at`.
+pub fn disk_light<T: Texture>(center: Point, diam: f64, look_at: Point, radiance: T) -> DynLight {
+	DynLight::new(DiskLight::new(center, diam, radiance).look_at(look_at))
+}
+
+impl<T: Texture> DiskLight<T> {
+	/// Disk light with given center, diameter,
+	/// and radiance (brightness of the surface), facing down.
+	pub fn new(center: Point, diam: f64, radiance: T) -> Self {
+		Self {
+			panel: Panel::new(center),
+			radius: diam / 2.0,
+			emissive: Emissive::new(radiance),
+		}
+	}
+
+	/// Turn the light so that its front side faces `target`.
+	pub fn look_at(self, target: Point) -> Self {
+		let dir = target - self.panel.center;
+		self.look_dir(dir)
+	}
+
+	/// Turn the light so that its front side faces direction `dir`.
+	pub fn look_dir(self, dir: Vector) -> Self {
+		Self {
+			panel: Panel::new_dir(self.panel.center, dir),
+			..self
+		}
+	}
+
+	/// Emitting surface area.
+	pub fn area(&self) -> f64 {
+		PI * self.radius * self.radius
+	}
+
+	// Texture coordinates at local coordinates (x, y).
+	fn tex_coords(&self, (x, y): (f64, f64)) -> Pointf {
+		let u = 0.5 + 0.5 * x / self.radius;
+		let v = 0.5 - 0.5 * y / self.radius;
+		Pointf(u as f32, v as f32, 0.0)
+	}
+
+	// Intersection distance and local coordinates, if the ray hits the disk (either side).
+	fn hit(&self, r: &Ray) -> Option<(f64, (f64, f64))> {
+		match self.panel.intersect(r) {
+			Some((t, (x, y))) if x * x + y * y <= self.radius * self.radius => Some((t, (x, y))),
+			_ => None,
+		}
+	}
+}
+
+impl<T: Texture> Bounded for DiskLight<T> {
+	fn bounds(&self) -> BoundingBox {
+		self.panel.bounds((self.radius, self.radius))
+	}
+}
+
+impl<T: Texture> Object for DiskLight<T> {
+	fn intersect<'s>(&'s self, r: &Ray, h: &mut HitRecord<'s>) {
+		if let Some((t, xy)) = self.hit(r) {
+			let n = self.panel.normal.into();
+			h.update_checked(t, n, n, self.tex_coords(xy), &self.emissive);
+		}
+	}
+
+	fn occluded(&self, r: &Ray, max_t: f64) -> Occlusion {
+		match self.hit(r) {
+			Some((t, _)) if t < max_t => Occlusion::Opaque,
+			_ => Occlusion::Clear,
+		}
+	}
+}
+
+impl<T: Texture> Light for DiskLight<T> {
+	fn sample(&self, rng: &mut Rng, target: Point) -> (Point, Color) {
+		let (x, y) = uniform_disk((rng.random() as f64, rng.random() as f64));
+		let xy = (self.radius * x, self.radius * y);
+		let pos = self.panel.at(xy);
+		let radiance = self.emissive.radiance(self.tex_coords(xy));
+		(pos, self.panel.intensity(self.area(), pos, radiance, target))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::test_rng;
+
+	#[test]
+	fn test_sample() {
+		// 20 cm diameter, facing +Z.
+		let light = DiskLight::new(Point(0., 0., 0.), 0.2, Color::WHITE).look_dir(Vector::EZ);
+		let mut trng = test_rng();
+		let mut rng = trng.for_pix((0, 0), 0);
+
+		// samples lie on the disk, spread uniformly around the center.
+		let target = Point(0., 0., 10.);
+		let mut avg = Vector::ZERO;
+		let n = 10000;
+		for _ in 0..n {
+			let (pos, intens) = light.sample(&mut rng, target);
+			assert!(pos[2].abs() < 1e-12 && pos.len() <= 0.1 + 1e-12);
+			let want = PI * 0.01 / (PI * 100.0);
+			assert!((intens.r() as f64 - want).abs() < 1e-3 * want);
+			avg += (pos - Point(0., 0., 0.)) * (1.0 / n as f64);
+		}
+		assert!(avg.len() < 2e-3);
+
+		// back side: dark
+		let (_, intens) = light.sample(&mut rng, Point(0., 0., -1.));
+		assert_eq!(intens, Color::BLACK);
+	}
+}
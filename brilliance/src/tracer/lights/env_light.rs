@@ -0,0 +1,221 @@
+use super::*;
+
+/// An environment light: a sky, or an outdoor (HDRI) photo surrounding the scene,
+/// seen by rays that miss all objects, and lighting the objects like any other light source.
+/// The scene's background is hidden behind it.
+///
+/// The texture is an equirectangular (latitude-longitude) map, wrapped around the scene
+/// by `UVMapped::sphere`. Light is sampled from directions chosen with probability
+/// proportional to the texture's luminance, so that e.g. a bright sun in the photo
+/// casts sharp, noise-free shadows. The luminance is tabulated on a grid of `resolution` bins,
+/// typically the size of the image.
+///
+///     use brilliance::*;
+///     let sky = EnvLight::new(Color::new(0.4, 0.6, 1.0), (1, 1));
+pub struct EnvLight<T: Texture> {
+	tex: UVMapped<T>,
+	dist: Distribution2D,
+}
+
+// Distance at which the environment is hit, and from which it is sampled:
+// effectively infinity, but finite, so that it can be told apart from
+// rays missing everything (see `Scene::lightfield_indirect`).
+pub(crate) const ENV_DIST: f64 = 1e30;
+
+/// Environment light from an equirectangular image.
+pub fn env_light<C>(img: Image<C>) -> DynLight
+where
+	C: Into<Color> + Copy + Default + Send + Sync + 'static,
+{
+	let resolution = img.dimensions();
+	DynLight::new(EnvLight::new(MipMap::new(img), resolution))
+}
+
+impl<T: Texture> EnvLight<T> {
+	/// Environment light with an equirectangular texture,
+	/// importance sampled on a grid of `resolution` (width, height) bins.
+	pub fn new(tex: T, (w, h): (u32, u32)) -> Self {
+		let (w, h) = (u32::max(1, w) as usize, u32::max(1, h) as usize);
+		// Bins are weighted by the brightest of 3x3 points, so that bright texels are not missed
+		// when bins and texels do not line up.
+		let lum = |i: usize, j: usize| {
+			let at = |s, t| {
+				tex.color_at(Pointf((i as f32 + s) / w as f32, (j as f32 + t) / h as f32, 0.0))
+					.luminance() as f64
+			};
+			let mut max = 0.0;
+			for &s in &[0.0, 0.5, 1.0] {
+				for &t in &[0.0, 0.5, 1.0] {
+					max = f64::max(max, at(s, t));
+				}
+			}
+			max
+		};
+		let mut weights: Vec<f64> = (0..h).flat_map(|j| (0..w).map(move |i| (i, j))).map(|(i, j)| lum(i, j)).collect();
+
+		// Dark parts of the texture still get a small probability.
+		let mean = weights.iter().sum::<f64>() / weights.len() as f64;
+		let floor = f64::max(1e-3 * mean, 1e-9);
+		for j in 0..h {
+			// rows near the poles cover less solid angle.
+			let sin = f64::sin(PI * (j as f64 + 0.5) / h as f64);
+			for x in &mut weights[j * w..(j + 1) * w] {
+				*x = (*x + floor) * sin;
+			}
+		}
+
+		Self {
+			tex: UVMapped::sphere(tex),
+			dist: Distribution2D::new(weights, (w, h)),
+		}
+	}
+}
+
+impl<T: Texture> Bounded for EnvLight<T> {
+	fn bounds(&self) -> BoundingBox {
+		BoundingBox::empty(Pointf::default())
+	}
+}
+
+impl<T: Texture> Object for EnvLight<T> {
+	fn intersect<'s>(&'s self, r: &Ray, h: &mut HitRecord<'s>) {
+		// hit by all rays that do not hit anything else.
+		let n: Vectorf = (-r.dir).into();
+		h.update_checked(ENV_DIST, n, n, r.dir.into(), self);
+	}
+
+	fn occluded(&self, _: &Ray, _: f64) -> Occlusion {
+		Occlusion::Clear
+	}
+}
+
+impl<T: Texture> Material for EnvLight<T> {
+	fn shade(&self, _: &Scene, _: &Ray, h: &HitCoords, _: &mut Rng, _: u32) -> Color {
+		self.tex.color_filtered(h.tex_coords, &h.footprint)
+	}
+}
+
+impl<T: Texture> Light for EnvLight<T> {
+	fn sample(&self, rng: &mut Rng, target: Point) -> (Point, Color) {
+		let ((u, v), pdf) = match self.dist.sample(rng) {
+			Some(s) => s,
+			None => return (target, Color::BLACK), // black environment
+		};
+
+		// inverse of `sphere_map`.
+		let phi = 2.0 * PI * (u - 0.5);
+		let lat = PI * (0.5 - v);
+		let dir = Vector(f64::cos(lat) * f64::cos(phi), f64::sin(lat), f64::cos(lat) * f64::sin(phi));
+
+		// radiance divided by the probability per solid angle, relative to Matte (divided by pi).
+		// An area du dv of the texture covers a solid angle 2 pi^2 cos(lat) du dv.
+		let radiance = self.tex.color_at(dir.into());
+		(target + ENV_DIST * dir, radiance * ((2.0 * PI * f64::cos(lat) / pdf) as f32))
+	}
+}
+
+// Piecewise constant probability distribution over the unit square,
+// with w x h bins of given weights (row by row).
+struct Distribution2D {
+	size: (usize, usize),
+	rows: Vec<f64>, // cumulative weight of rows
+	cols: Vec<f64>, // cumulative weight of bins, per row
+}
+
+impl Distribution2D {
+	fn new(weights: Vec<f64>, (w, h): (usize, usize)) -> Self {
+		let mut cols = weights;
+		let mut rows = Vec::with_capacity(h);
+		let mut total = 0.0;
+		for row in cols.chunks_mut(w) {
+			let mut acc = 0.0;
+			for c in row {
+				acc += *c;
+				*c = acc;
+			}
+			total += acc;
+			rows.push(total);
+		}
+		Self { size: (w, h), rows, cols }
+	}
+
+	// A random point (u, v), and the probability density there.
+	// None if all weights are zero.
+	fn sample(&self, rng: &mut Rng) -> Option<((f64, f64), f64)> {
+		let (w, h) = self.size;
+		let total = *self.rows.last().unwrap();
+		if total <= 0.0 {
+			return None;
+		}
+
+		// row with probability proportional to its weight, then a bin within that row.
+		let pick = |cdf: &[f64], x: f64| usize::min(cdf.partition_point(|&c| c <= x), cdf.len() - 1);
+		let j = pick(&self.rows, rng.random() as f64 * total);
+		let row = &self.cols[j * w..(j + 1) * w];
+		let i = pick(row, rng.random() as f64 * row[w - 1]);
+		let weight = row[i] - if i == 0 { 0.0 } else { row[i - 1] };
+
+		// uniform within the bin.
+		let u = (i as f64 + rng.random() as f64) / w as f64;
+		let v = (j as f64 + rng.random() as f64) / h as f64;
+		Some(((u, v), weight / total * (w * h) as f64))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::test_rng;
+
+	// Uniform sky: irradiance on a horizontal surface is pi times the radiance,
+	// so 1 relative to Matte.
+	#[test]
+	fn test_uniform() {
+		let light = EnvLight::new(Color::WHITE, (16, 8));
+		let mut trng = test_rng();
+		let mut rng = trng.for_pix((0, 0), 0);
+		let n = 100000;
+		let mut sum = 0.0;
+		for _ in 0..n {
+			let (pos, intens) = light.sample(&mut rng, Point(0., 0., 0.));
+			let cos = pos.normalized()[1];
+			if cos > 0.0 {
+				sum += intens.r() as f64 * cos;
+			}
+		}
+		let avg = sum / n as f64;
+		assert!((avg - 1.0).abs() < 0.02, "{}", avg);
+	}
+
+	// A small, bright sun in a dark sky: nearly all samples are aimed at it.
+	#[test]
+	fn test_sun() {
+		let (w, h) = (64, 32);
+		let img = Image::<Color>::from_fn((w, h), |x, y| if (x, y) == (16, 8) { Color::WHITE } else { Color::BLACK });
+		let light = EnvLight::new(img, (w, h));
+		let mut trng = test_rng();
+		let mut rng = trng.for_pix((0, 0), 0);
+
+		// sun direction, see `sphere_map`.
+		let (u, v) = (16.0 / (w - 1) as f64, 8.0 / (h - 1) as f64);
+		let (phi, lat) = (2.0 * PI * (u - 0.5), PI * (0.5 - v));
+		let sun = Vector(f64::cos(lat) * f64::cos(phi), f64::sin(lat), f64::cos(lat) * f64::sin(phi));
+
+		let n = 1000;
+		let near = (0..n)
+			.filter(|_| light.sample(&mut rng, Point(0., 0., 0.)).0.normalized().dot(sun) > 0.99)
+			.count();
+		assert!(near > 9 * n / 10, "{}", near);
+	}
+
+	#[test]
+	fn test_intersect() {
+		let light = EnvLight::new(Color::WHITE, (1, 1));
+		let background = Flat::new(Color::BLACK);
+		let r = Ray::new(Point(0., 0., 0.), Vector::EX);
+		let mut h = HitRecord::background(&background, r.dir.into());
+		light.intersect(&r, &mut h);
+		assert_eq!(h.t(), ENV_DIST);
+		assert_eq!(light.occluded(&r, INF), Occlusion::Clear);
+	}
+}
@@ -0,0 +1,203 @@
+use super::*;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// A photometric profile from an IES (IESNA LM-63) file, as published by
+/// lamp manufacturers. It gives the luminous intensity of a luminaire in every direction,
+/// e.g. the bright rim and dark center of a downlight, or the scalloped pattern
+/// of a wall washer. See `SpotLight::with_ies`.
+///
+/// Only type C photometry (the usual one for architectural lighting) is supported:
+/// vertical angles are measured from the nadir (straight down the luminaire's axis),
+/// horizontal angles around it.
+#[derive(Clone, Debug)]
+pub struct IesProfile {
+	vertical: Vec<f64>,   // vertical angles, radians, increasing
+	horizontal: Vec<f64>, // horizontal angles, radians, increasing
+	candela: Vec<f64>,    // intensity per horizontal angle, per vertical angle
+	max: f64,             // largest candela value
+}
+
+/// Parse an IES photometric file.
+pub fn parse_ies_file(fname: &Path) -> Result<IesProfile> {
+	parse_ies(File::open(fname)?)
+}
+
+/// Like parse_ies_file, but accepts a reader.
+pub fn parse_ies<R: Read>(r: R) -> Result<IesProfile> {
+	// Keywords up to the TILT line are ignored.
+	// After that, numbers are separated by whitespace or commas, regardless of line breaks.
+	let mut lines = BufReader::new(r).lines();
+	let tilt = loop {
+		match lines.next() {
+			None => return error("IES: missing TILT line".to_owned()),
+			Some(line) => {
+				let line = line?;
+				if let Some(tilt) = line.trim().strip_prefix("TILT=") {
+					break tilt.to_owned();
+				}
+			}
+		}
+	};
+	let mut numbers = Vec::new();
+	for line in lines {
+		for word in line?.split(|c: char| c.is_whitespace() || c == ',').filter(|w| !w.is_empty()) {
+			match word.parse::<f64>() {
+				Ok(v) => numbers.push(v),
+				Err(_) => return error(format!("IES: need a number, got: {}", word)),
+			}
+		}
+	}
+	let mut numbers = numbers.into_iter();
+	let mut next = || match numbers.next() {
+		Some(v) => Ok(v),
+		None => error("IES: unexpected end of file".to_owned()),
+	};
+
+	match tilt.as_str() {
+		"NONE" => (),
+		"INCLUDE" => {
+			// lamp-to-luminaire geometry, then angles and multiplying factors: not used.
+			next()?;
+			let n = next()? as usize;
+			for _ in 0..(2 * n) {
+				next()?;
+			}
+		}
+		_ => return error(format!("IES: unsupported TILT: {}", tilt)),
+	}
+
+	let _lamps = next()?;
+	let _lumens = next()?;
+	let multiplier = next()?;
+	let num_vertical = next()? as usize;
+	let num_horizontal = next()? as usize;
+	let photometric_type = next()?;
+	for _ in 0..7 {
+		next()?; // units, width, length, height, ballast factor, reserved, input watts
+	}
+	if photometric_type != 1.0 {
+		return error(format!("IES: only type C photometry is supported, got type: {}", photometric_type));
+	}
+	if num_vertical == 0 || num_horizontal == 0 {
+		return error("IES: need at least one vertical and horizontal angle".to_owned());
+	}
+
+	let mut angles = |n| (0..n).map(|_| Ok(next()? * DEG)).collect::<Result<Vec<f64>>>();
+	let vertical = angles(num_vertical)?;
+	let horizontal = angles(num_horizontal)?;
+	let mut candela = Vec::with_capacity(num_vertical * num_horizontal);
+	for _ in 0..(num_vertical * num_horizontal) {
+		candela.push(next()? * multiplier);
+	}
+	let max = candela.iter().copied().fold(0.0, f64::max);
+
+	Ok(IesProfile {
+		vertical,
+		horizontal,
+		candela,
+		max,
+	})
+}
+
+impl IesProfile {
+	/// Intensity in direction (vertical angle `theta` from the nadir, horizontal angle `phi`),
+	/// relative to the brightest direction. Linearly interpolated between the tabulated angles.
+	pub fn eval(&self, theta: f64, phi: f64) -> f64 {
+		if self.max == 0.0 {
+			return 0.0;
+		}
+
+		// Fewer horizontal angles are given for symmetric luminaires,
+		// the last angle tells which symmetry.
+		let last = *self.horizontal.last().unwrap();
+		let phi = phi.rem_euclid(2.0 * PI);
+		let phi = if last < 1e-6 {
+			0.0 // rotationally symmetric
+		} else if (last - PI / 2.0).abs() < 1e-6 {
+			let phi = phi % PI; // symmetric in each quadrant
+			if phi > PI / 2.0 {
+				PI - phi
+			} else {
+				phi
+			}
+		} else if (last - PI).abs() < 1e-6 && phi > PI {
+			2.0 * PI - phi // symmetric about the 0-180 degree plane
+		} else {
+			phi
+		};
+
+		// no light outside of the tabulated vertical angles (e.g. above a downlight).
+		if theta < self.vertical[0] || theta > *self.vertical.last().unwrap() {
+			return 0.0;
+		}
+
+		let (h0, h1, wh) = interp(&self.horizontal, phi);
+		let (v0, v1, wv) = interp(&self.vertical, theta);
+		let at = |h: usize, v: usize| self.candela[h * self.vertical.len() + v];
+		let c0 = at(h0, v0) * (1.0 - wv) + at(h0, v1) * wv;
+		let c1 = at(h1, v0) * (1.0 - wv) + at(h1, v1) * wv;
+		(c0 * (1.0 - wh) + c1 * wh) / self.max
+	}
+}
+
+// Indices of the tabulated angles around x, and the weight of the second one.
+// Clamped to the first and last angle.
+fn interp(angles: &[f64], x: f64) -> (usize, usize, f64) {
+	let i = angles.partition_point(|&a| a <= x);
+	if i == 0 {
+		return (0, 0, 0.0);
+	}
+	if i == angles.len() {
+		return (i - 1, i - 1, 0.0);
+	}
+	let (a0, a1) = (angles[i - 1], angles[i]);
+	(i - 1, i, (x - a0) / (a1 - a0))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_parse_ies() {
+		// a downlight, brightest at 30 degrees, symmetric around its axis.
+		let input = r"IESNA:LM-63-2002
+[MANUFAC] Test
+TILT=NONE
+1 1000 2 4 1 1 2 0.1 0.1 0
+1.0 1.0 10
+0 30 60 90
+0
+50, 100, 25
+0
+"
+		.as_bytes();
+		let ies = parse_ies(input).unwrap();
+		assert_eq!(ies.max, 200.0);
+		assert_eq!(ies.eval(30.0 * DEG, 0.0), 1.0);
+		assert_eq!(ies.eval(30.0 * DEG, 123.0 * DEG), 1.0);
+		assert!((ies.eval(15.0 * DEG, 0.0) - 0.75).abs() < 1e-9);
+		assert_eq!(ies.eval(0.0, 0.0), 0.5);
+		assert_eq!(ies.eval(120.0 * DEG, 0.0), 0.0);
+
+		assert!(parse_ies("TILT=NONE\n1 1000 1 4 1 1 2 0 0 0\n".as_bytes()).is_err());
+		assert!(parse_ies("TILT=lamp.tlt\n".as_bytes()).is_err());
+		assert!(parse_ies("no tilt\n".as_bytes()).is_err());
+	}
+
+	#[test]
+	fn test_symmetry() {
+		// quadrant symmetric: 0 and 90 degrees given.
+		let input = "TILT=NONE\n1 -1 1 2 2 1 2 0 0 0\n1 1 0\n0 90\n0 90\n1 1\n2 2\n".as_bytes();
+		let ies = parse_ies(input).unwrap();
+		for &phi in &[0.0, 180.0, 360.0] {
+			assert!((ies.eval(0.0, phi * DEG) - 0.5).abs() < 1e-9);
+		}
+		for &phi in &[90.0, 270.0] {
+			assert!((ies.eval(0.0, phi * DEG) - 1.0).abs() < 1e-9);
+		}
+		assert!((ies.eval(0.0, 135.0 * DEG) - 0.75).abs() < 1e-9);
+	}
+}
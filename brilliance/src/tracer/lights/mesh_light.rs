@@ -0,0 +1,163 @@
+use super::*;
+use std::sync::Arc;
+
+/// A triangle mesh with an `Emissive` material, used as a light source.
+/// E.g. light panels, screens or neon tubes modeled in an OBJ file
+/// (see `parse_file_faces`).
+///
+/// Light is sampled from random points on the surface, chosen uniformly by area,
+/// which gives soft shadows and much less noise than lighting
+/// by an emissive object alone.
+/// Like `Emissive`, only the front side of each triangle emits light.
+///
+///     use brilliance::*;
+///     let v = |x, z| Vertex { pos: Pointf(x, 2.0, z), attr: Attr::default() };
+///     // facing down
+///     let panel = vec![Face::new(v(0.0, 0.0), v(1.0, 0.0), v(0.0, 1.0)), Face::new(v(1.0, 0.0), v(1.0, 1.0), v(0.0, 1.0))];
+///     let light = mesh_light(panel, WHITE.ev(3.0));
+pub struct MeshLight<T: Texture> {
+	object: WithMaterial<QTree<Face>, Arc<Emissive<T>>>,
+	emissive: Arc<Emissive<T>>,
+	triangles: Vec<Triangle>,
+	cdf: Vec<f64>, // cumulative area of triangles
+}
+
+// A triangle, stored for sampling.
+struct Triangle {
+	o: Point,
+	a: Vector,
+	b: Vector,
+	normal: Vector,
+	tex_coords: [Pointf; 3],
+}
+
+pub fn mesh_light<T: Texture>(faces: Vec<Face>, radiance: T) -> DynLight {
+	DynLight::new(MeshLight::new(faces, radiance))
+}
+
+impl<T: Texture> MeshLight<T> {
+	/// Light source emitting `radiance` (a texture) from the front side of `faces`.
+	pub fn new(faces: Vec<Face>, radiance: T) -> Self {
+		let mut triangles = Vec::with_capacity(faces.len());
+		let mut cdf = Vec::with_capacity(faces.len());
+		let mut area = 0.0;
+		for f in &faces {
+			let a: Vector = f.a().into();
+			let b: Vector = f.b().into();
+			let cross = a.cross(b);
+			if cross.len2() == 0.0 {
+				continue; // degenerate
+			}
+			area += cross.len() / 2.0;
+			cdf.push(area);
+			let attr = f.attrs();
+			triangles.push(Triangle {
+				o: f.o().into(),
+				a,
+				b,
+				normal: cross.normalized(),
+				tex_coords: [attr[0].tex_coords.into(), attr[1].tex_coords.into(), attr[2].tex_coords.into()],
+			});
+		}
+
+		let emissive = Arc::new(Emissive::new(radiance));
+		Self {
+			object: QTree::new(faces).paint(emissive.clone()),
+			emissive,
+			triangles,
+			cdf,
+		}
+	}
+
+	/// Total emitting surface area.
+	pub fn area(&self) -> f64 {
+		self.cdf.last().copied().unwrap_or_default()
+	}
+}
+
+impl<T: Texture> Bounded for MeshLight<T> {
+	fn bounds(&self) -> BoundingBox {
+		self.object.bounds()
+	}
+}
+
+impl<T: Texture> Object for MeshLight<T> {
+	fn intersect<'s>(&'s self, r: &Ray, h: &mut HitRecord<'s>) {
+		self.object.intersect(r, h)
+	}
+
+	fn occluded(&self, r: &Ray, max_t: f64) -> Occlusion {
+		self.object.occluded(r, max_t)
+	}
+}
+
+impl<T: Texture> Light for MeshLight<T> {
+	fn sample(&self, rng: &mut Rng, target: Point) -> (Point, Color) {
+		let area = self.area();
+		if area == 0.0 {
+			return (target, Color::BLACK);
+		}
+
+		// pick a triangle with probability proportional to its area,
+		// and a uniformly random point on it.
+		let x = (rng.random() as f64) * area;
+		let i = usize::min(self.cdf.partition_point(|&c| c <= x), self.triangles.len() - 1);
+		let tri = &self.triangles[i];
+		let (b1, b2) = uniform_triangle((rng.random() as f64, rng.random() as f64));
+		let pos = tri.o + b1 * tri.a + b2 * tri.b;
+
+		let delta = target - pos;
+		let dist2 = delta.len2();
+		let cos = tri.normal.dot(delta) / dist2.sqrt();
+		if cos <= 0.0 {
+			return (pos, Color::BLACK); // target behind the light
+		}
+
+		let t = &tri.tex_coords;
+		let (b1, b2) = (b1 as f32, b2 as f32);
+		let tex_coords = t[0] * (1.0 - b1 - b2) + t[1] * b1 + t[2] * b2;
+
+		// radiance times the solid angle of the whole light, as seen from the target (1/pdf),
+		// relative to Matte (divided by pi).
+		let solid_angle = cos * area / dist2;
+		(pos, self.emissive.radiance(tex_coords) * ((solid_angle / PI) as f32))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::test_rng;
+
+	// A small emitter, seen from a distance, behaves like a point light.
+	#[test]
+	fn test_sample() {
+		let v = |x, z| Vertex {
+			pos: Pointf(x, 0.0, z),
+			attr: Attr::default(),
+		};
+		// 10 cm square, facing down.
+		let s = 0.05;
+		let faces = vec![Face::new(v(-s, -s), v(s, -s), v(-s, s)), Face::new(v(s, -s), v(s, s), v(-s, s))];
+		let light = MeshLight::new(faces, Color::WHITE);
+		assert!((light.area() - 0.01).abs() < 1e-6);
+
+		let mut trng = test_rng();
+		let mut rng = trng.for_pix((0, 0), 0);
+		let target = Point(0.0, -10.0, 0.0);
+		let n = 1000;
+		let mut sum = 0.0;
+		for _ in 0..n {
+			let (pos, intens) = light.sample(&mut rng, target);
+			assert!(pos[1] == 0.0 && pos[0].abs() < 0.051 && pos[2].abs() < 0.051);
+			sum += intens.r() as f64;
+		}
+		let avg = sum / n as f64;
+		let want = 0.01 / (PI * 100.0);
+		assert!((avg - want).abs() < 1e-3 * want, "{} {}", avg, want);
+
+		// back side: dark
+		let (_, intens) = light.sample(&mut rng, Point(0.0, 1.0, 0.0));
+		assert_eq!(intens, Color::BLACK);
+	}
+}
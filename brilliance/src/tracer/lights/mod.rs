@@ -1,9 +1,24 @@
+pub mod directional_light;
+pub mod disk_light;
+pub mod env_light;
+pub mod ies;
+pub mod mesh_light;
+mod panel;
 pub mod point_light;
+pub mod rect_light;
 pub mod sphere_light;
+pub mod spot_light;
 pub mod with_object;
 
+pub use directional_light::*;
+pub use disk_light::*;
+pub use env_light::*;
+pub use ies::*;
+pub use mesh_light::*;
 pub use point_light::*;
+pub use rect_light::*;
 pub use sphere_light::*;
+pub use spot_light::*;
 pub use with_object::*;
 
 pub use super::*;
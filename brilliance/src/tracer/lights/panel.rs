@@ -0,0 +1,67 @@
+use super::*;
+
+// Position and orientation of a flat, one-sided light source
+// (see `RectLight`, `DiskLight`), or of a `SpotLight`'s beam:
+// a center, the emitting normal, and two unit axes spanning the plane.
+#[derive(Clone)]
+pub(crate) struct Panel {
+	pub center: Point,
+	pub ex: Vector,
+	pub ey: Vector,
+	pub normal: Vector,
+}
+
+impl Panel {
+	// Panel facing down, like a ceiling light.
+	pub fn new(center: Point) -> Self {
+		Self::new_dir(center, -Vector::EY)
+	}
+
+	// Panel facing `dir`. The x axis is kept horizontal (perpendicular to Y) if possible.
+	pub fn new_dir(center: Point, dir: Vector) -> Self {
+		let normal = dir.normalized();
+		let ex = Vector::EY.cross(normal);
+		let ex = if ex.len() < 1e-6 { Vector::EX } else { ex.normalized() };
+		let ey = normal.cross(ex);
+		Self { center, ex, ey, normal }
+	}
+
+	// World position of local coordinates (x, y).
+	pub fn at(&self, (x, y): (f64, f64)) -> Point {
+		self.center + x * self.ex + y * self.ey
+	}
+
+	// Distance along the ray to the panel's plane,
+	// and local coordinates of the intersection point.
+	pub fn intersect(&self, r: &Ray) -> Option<(f64, (f64, f64))> {
+		let dn = r.dir.dot(self.normal);
+		if dn == 0.0 {
+			return None;
+		}
+		let t = (self.center - r.start).dot(self.normal) / dn;
+		if t <= 0.0 || t.is_nan() {
+			return None;
+		}
+		let d = r.at(t) - self.center;
+		Some((t, (d.dot(self.ex), d.dot(self.ey))))
+	}
+
+	// Bounding box of the rectangle with half sizes (w, h) around the center.
+	pub fn bounds(&self, (w, h): (f64, f64)) -> BoundingBox {
+		let corners: Vec<Pointf> = [(-w, -h), (w, -h), (w, h), (-w, h)].iter().map(|&xy| self.at(xy).into()).collect();
+		BoundingBox::from_points(corners.iter())
+	}
+
+	// Intensity at `target`, of a panel with given `area`, uniformly sampled at `pos`,
+	// with given `radiance` there: radiance times the solid angle of the whole panel
+	// as seen from the target (1/pdf), relative to Matte (divided by pi).
+	pub fn intensity(&self, area: f64, pos: Point, radiance: Color, target: Point) -> Color {
+		let delta = target - pos;
+		let dist2 = delta.len2();
+		let cos = self.normal.dot(delta) / dist2.sqrt();
+		if cos <= 0.0 {
+			return Color::BLACK; // target behind the light
+		}
+		radiance * ((cos * area / (dist2 * PI)) as f32)
+	}
+}
@@ -1,5 +1,13 @@
 use super::*;
 
+/// An infinitely small light source, casting sharp shadows.
+///
+/// `power` is the total flux emitted in all directions (e.g. `lumens`).
+/// The light falls off with the inverse square distance, like a `sphere_light`
+/// of the same power. The light itself is not visible to the camera.
+///
+///     use brilliance::*;
+///     let bulb = PointLight::new(Point(0., 2., 0.), lumens(WHITE, 800.0));
 pub struct PointLight {
 	pos: Point,
 	power: Color,
@@ -17,19 +25,39 @@ impl PointLight {
 
 impl Bounded for PointLight {
 	fn bounds(&self) -> BoundingBox {
-		BoundingBox::empty(Pointf::default())
+		BoundingBox::empty(self.pos.into())
 	}
 }
 
 impl Object for PointLight {
-	fn intersect<'s>(&'s self, r: &Ray, h: &mut HitRecord<'s>) {
+	fn intersect<'s>(&'s self, _: &Ray, _: &mut HitRecord<'s>) {
 		// never intersects
 	}
+
+	fn occluded(&self, _: &Ray, _: f64) -> Occlusion {
+		Occlusion::Clear
+	}
 }
 
 impl Light for PointLight {
 	fn sample(&self, _: &mut Rng, target: Point) -> (Point, Color) {
-		//(self.pos, self.power * (((1. / (4. * PI)) / ((target - self.pos).len2())) as f32))
-		(self.pos, self.power) // planetarium hack
+		// power spread evenly over the sphere around the light.
+		(self.pos, self.power * (((1. / (4. * PI)) / ((target - self.pos).len2())) as f32))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::test_rng;
+
+	#[test]
+	fn test_falloff() {
+		let light = PointLight::new(Point(1., 2., 3.), Color::WHITE);
+		let mut trng = test_rng();
+		let mut rng = trng.for_pix((0, 0), 0);
+		let at = |rng: &mut Rng, dist: f64| light.sample(rng, Point(1., 2. - dist, 3.)).1.r() as f64;
+		assert!((at(&mut rng, 1.0) - 1.0 / (4.0 * PI)).abs() < 1e-6);
+		assert!((at(&mut rng, 1.0) / at(&mut rng, 10.0) - 100.0).abs() < 1e-3);
 	}
 }
@@ -0,0 +1,146 @@
+use super::panel::*;
+use super::*;
+
+/// A rectangular, one-sided light source, like a softbox, window or ceiling panel.
+/// It casts soft shadows, and is visible to the camera.
+///
+/// The light faces down (-Y) by default, `look_at` or `look_dir` turn it.
+/// Its width is along the horizontal axis (perpendicular to Y, if possible),
+/// its height along the remaining axis. Only the front side emits light,
+/// like `Emissive`. The emitted radiance may be a texture, e.g. an image for a screen,
+/// mapped to the rectangle with (0, 0) at the top left, as seen from the front.
+///
+/// Light is sampled from uniformly random points on the rectangle.
+///
+///     use brilliance::*;
+///     let softbox = RectLight::new(Point(0., 2., 1.), (1.0, 0.5), WHITE.ev(4.0)).look_at(Point(0., 0., 0.));
+pub struct RectLight<T: Texture> {
+	panel: Panel,
+	half: (f64, f64), // half width, half height
+	emissive: Emissive<T>,
+}
+
+/// Rectangular light with given center, size (width, height) and radiance,
+/// turned to face `look_at`.
+pub fn rect_light<T: Texture>(center: Point, size: (f64, f64), look_at: Point, radiance: T) -> DynLight {
+	DynLight::new(RectLight::new(center, size, radiance).look_at(look_at))
+}
+
+impl<T: Texture> RectLight<T> {
+	/// Rectangular light with given center, size (width, height),
+	/// and radiance (brightness of the surface), facing down.
+	pub fn new(center: Point, (width, height): (f64, f64), radiance: T) -> Self {
+		Self {
+			panel: Panel::new(center),
+			half: (width / 2.0, height / 2.0),
+			emissive: Emissive::new(radiance),
+		}
+	}
+
+	/// Turn the light so that its front side faces `target`.
+	pub fn look_at(self, target: Point) -> Self {
+		let dir = target - self.panel.center;
+		self.look_dir(dir)
+	}
+
+	/// Turn the light so that its front side faces direction `dir`.
+	pub fn look_dir(self, dir: Vector) -> Self {
+		Self {
+			panel: Panel::new_dir(self.panel.center, dir),
+			..self
+		}
+	}
+
+	/// Emitting surface area.
+	pub fn area(&self) -> f64 {
+		4.0 * self.half.0 * self.half.1
+	}
+
+	// Texture coordinates at local coordinates (x, y).
+	fn tex_coords(&self, (x, y): (f64, f64)) -> Pointf {
+		let u = 0.5 + 0.5 * x / self.half.0;
+		let v = 0.5 - 0.5 * y / self.half.1;
+		Pointf(u as f32, v as f32, 0.0)
+	}
+
+	// Intersection distance and local coordinates, if the ray hits the rectangle (either side).
+	fn hit(&self, r: &Ray) -> Option<(f64, (f64, f64))> {
+		match self.panel.intersect(r) {
+			Some((t, (x, y))) if x.abs() <= self.half.0 && y.abs() <= self.half.1 => Some((t, (x, y))),
+			_ => None,
+		}
+	}
+}
+
+impl<T: Texture> Bounded for RectLight<T> {
+	fn bounds(&self) -> BoundingBox {
+		self.panel.bounds(self.half)
+	}
+}
+
+impl<T: Texture> Object for RectLight<T> {
+	fn intersect<'s>(&'s self, r: &Ray, h: &mut HitRecord<'s>) {
+		if let Some((t, xy)) = self.hit(r) {
+			let n = self.panel.normal.into();
+			h.update_checked(t, n, n, self.tex_coords(xy), &self.emissive);
+		}
+	}
+
+	fn occluded(&self, r: &Ray, max_t: f64) -> Occlusion {
+		match self.hit(r) {
+			Some((t, _)) if t < max_t => Occlusion::Opaque,
+			_ => Occlusion::Clear,
+		}
+	}
+}
+
+impl<T: Texture> Light for RectLight<T> {
+	fn sample(&self, rng: &mut Rng, target: Point) -> (Point, Color) {
+		let (u, v) = (rng.random() as f64, rng.random() as f64);
+		let xy = ((2.0 * u - 1.0) * self.half.0, (2.0 * v - 1.0) * self.half.1);
+		let pos = self.panel.at(xy);
+		let radiance = self.emissive.radiance(self.tex_coords(xy));
+		(pos, self.panel.intensity(self.area(), pos, radiance, target))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::test_rng;
+
+	#[test]
+	fn test_sample() {
+		// 10 x 20 cm, facing down.
+		let light = RectLight::new(Point(0., 0., 0.), (0.1, 0.2), Color::WHITE);
+		let mut trng = test_rng();
+		let mut rng = trng.for_pix((0, 0), 0);
+
+		// seen from a distance: like a point light with the cosine falloff of a flat emitter.
+		let target = Point(0., -10., 0.);
+		for _ in 0..100 {
+			let (pos, intens) = light.sample(&mut rng, target);
+			assert!(pos[1] == 0.0 && pos[0].abs() <= 0.05 && pos[2].abs() <= 0.1);
+			let want = 0.02 / (PI * 100.0);
+			assert!((intens.r() as f64 - want).abs() < 1e-3 * want);
+		}
+		// back side: dark
+		let (_, intens) = light.sample(&mut rng, Point(0., 1., 0.));
+		assert_eq!(intens, Color::BLACK);
+
+		// turned to face +X, like a window in the wall.
+		let light = light.look_dir(Vector::EX);
+		let (pos, intens) = light.sample(&mut rng, Point(10., 0., 0.));
+		assert!(pos[0].abs() < 1e-12 && intens != Color::BLACK);
+	}
+
+	#[test]
+	fn test_intersect() {
+		let light = RectLight::new(Point(0., 2., 0.), (2.0, 1.0), Color::WHITE);
+		let up = |x, z| Ray::new(Point(x, 0., z), Vector::EY);
+		assert_eq!(light.occluded(&up(0.9, 0.4), 10.0), Occlusion::Opaque);
+		assert_eq!(light.occluded(&up(0.9, 0.4), 1.0), Occlusion::Clear);
+		assert_eq!(light.occluded(&up(1.1, 0.0), 10.0), Occlusion::Clear);
+		assert_eq!(light.occluded(&up(0.0, 0.6), 10.0), Occlusion::Clear);
+	}
+}
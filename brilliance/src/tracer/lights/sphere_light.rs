@@ -1,10 +1,125 @@
 use super::*;
 
+/// A spherical light source, like a light bulb or the sun.
+/// Unlike a `PointLight`, it casts soft shadows, with a penumbra
+/// as wide as the sphere appears from the shadow.
+///
+/// Light is sampled from the cone of directions that the sphere subtends,
+/// as seen from the target, so that even distant or small spheres
+/// are sampled without wasting rays on their back side.
+///
+///     use brilliance::*;
+///     let bulb = SphereLight::new(Point(0., 3., 0.), 0.1, WHITE.ev(8.0));
+pub struct SphereLight {
+	center: Point,
+	radius: f64,
+	radiance: Color,
+	object: WithMaterial<Sphere, Flat<Color>>,
+}
+
+/// Sphere light with given center, diameter and total emitted power
+/// (e.g. `lumens`). From a distance, it is as bright as a `PointLight` of the same power.
 pub fn sphere_light(pos: Point, diam: f64, power: Color) -> DynLight {
 	let r = diam / 2.0;
-	let brilliance = power / ((4.0 * PI * r * r) as f32);
-	DynLight::new(WithObject::new(
-		PointLight::new(pos, power), // TODO
-		Sphere::new(pos, diam).paint(Flat::new(brilliance)),
-	))
+	let radiance = power / ((4.0 * PI * r * r) as f32);
+	DynLight::new(SphereLight::new(pos, diam, radiance))
+}
+
+impl SphereLight {
+	/// Sphere light with given center, diameter, and radiance (brightness of the surface).
+	pub fn new(center: Point, diam: f64, radiance: Color) -> Self {
+		Self {
+			center,
+			radius: diam / 2.0,
+			radiance,
+			object: Sphere::new(center, diam).paint(Flat::new(radiance)),
+		}
+	}
+}
+
+impl Bounded for SphereLight {
+	fn bounds(&self) -> BoundingBox {
+		self.object.bounds()
+	}
+}
+
+impl Object for SphereLight {
+	fn intersect<'s>(&'s self, r: &Ray, h: &mut HitRecord<'s>) {
+		self.object.intersect(r, h)
+	}
+
+	fn occluded(&self, r: &Ray, max_t: f64) -> Occlusion {
+		self.object.occluded(r, max_t)
+	}
+}
+
+impl Light for SphereLight {
+	fn sample(&self, rng: &mut Rng, target: Point) -> (Point, Color) {
+		let delta = self.center - target;
+		let dist2 = delta.len2();
+		let r2 = self.radius * self.radius;
+		if dist2 <= r2 {
+			return (self.center, Color::BLACK); // inside the light
+		}
+
+		// cone of directions towards the sphere, with half-angle theta_max.
+		// 1 - cos(theta_max) is computed as sin^2 / (1 + cos), accurate for small (distant) spheres.
+		let dist = dist2.sqrt();
+		let sin2_max = r2 / dist2;
+		let cos_max = f64::sqrt(1.0 - sin2_max);
+		let one_minus_cos_max = sin2_max / (1.0 + cos_max);
+
+		// uniform direction inside the cone.
+		let (u, v) = (rng.random() as f64, rng.random() as f64);
+		let one_minus_cos = u * one_minus_cos_max;
+		let cos = 1.0 - one_minus_cos;
+		let sin2 = f64::max(0.0, one_minus_cos * (2.0 - one_minus_cos));
+		let sin = sin2.sqrt();
+		let phi = 2.0 * PI * v;
+		let dir = make_basis(delta / dist) * Vector(sin * f64::cos(phi), sin * f64::sin(phi), cos);
+
+		// nearest intersection of that direction with the sphere.
+		let t = dist * cos - f64::sqrt(f64::max(0.0, r2 - dist2 * sin2));
+		let pos = target + t * dir;
+
+		// radiance times the solid angle of the cone (1/pdf), relative to Matte (divided by pi).
+		let solid_angle = 2.0 * PI * one_minus_cos_max;
+		(pos, self.radiance * ((solid_angle / PI) as f32))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::test_rng;
+
+	#[test]
+	fn test_sample() {
+		let light = SphereLight::new(Point(0., 0., 0.), 2.0, Color::WHITE);
+		let mut trng = test_rng();
+		let mut rng = trng.for_pix((0, 0), 0);
+
+		// seen from distance 2, the sphere subtends a cone with half-angle 30 degrees.
+		let target = Point(0., 0., 2.);
+		let mut avg_dir = Vector::ZERO;
+		let n = 10000;
+		for _ in 0..n {
+			let (pos, intens) = light.sample(&mut rng, target);
+			// on the sphere, on the side facing the target.
+			assert!((pos.len() - 1.0).abs() < 1e-9);
+			assert!(pos.dot(target - pos) >= -1e-9);
+			// solid angle 2 pi (1 - cos 30 deg), divided by pi.
+			let want = 2.0 * (1.0 - f64::sqrt(3.0) / 2.0);
+			assert!((intens.r() as f64 - want).abs() < 1e-6);
+			avg_dir += (pos - target).normalized() * (1.0 / n as f64);
+		}
+		// directions are spread uniformly around the center.
+		assert!((avg_dir.normalized() - Vector(0., 0., -1.)).len() < 1e-2);
+
+		// from far away: like a point light with inverse square falloff.
+		let light = sphere_light(Point(0., 0., 0.), 0.01, Color::WHITE);
+		let (_, intens) = light.sample(&mut rng, Point(1000., 0., 0.));
+		let want = 1.0 / (4.0 * PI * 1000.0 * 1000.0);
+		assert!((intens.r() as f64 - want).abs() < 1e-6 * want);
+	}
 }
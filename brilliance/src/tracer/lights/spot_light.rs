@@ -0,0 +1,156 @@
+use super::panel::*;
+use super::*;
+
+/// A point light shining in a cone, like a stage light or a recessed downlight.
+///
+/// Inside the inner cone, it is as bright as a `sphere_light` with the same power,
+/// falling off with the inverse square distance. Between the inner and outer cone,
+/// the brightness fades out smoothly. Outside the outer cone, there is no light.
+/// Cone angles are measured from the axis, i.e. half the opening angle.
+///
+/// The light faces down (-Y) by default, `look_at` or `look_dir` turn it.
+/// `with_ies` shapes the beam like a real luminaire, given its photometric (IES) file.
+/// The light itself is not visible to the camera.
+///
+///     use brilliance::*;
+///     let spot = SpotLight::new(Point(0., 3., 0.), WHITE.ev(10.0)).look_at(Point(1., 0., 0.)).with_cone(15.0 * DEG, 20.0 * DEG);
+pub struct SpotLight {
+	frame: Panel,
+	cos_inner: f64,
+	cos_outer: f64,
+	power: Color,
+	ies: Option<IesProfile>,
+}
+
+/// Spot light with given position, inner and outer cone angle and power, turned to face `look_at`.
+pub fn spot_light(pos: Point, look_at: Point, (inner, outer): (f64, f64), power: Color) -> DynLight {
+	DynLight::new(SpotLight::new(pos, power).look_at(look_at).with_cone(inner, outer))
+}
+
+impl SpotLight {
+	/// Spot light with given position and power, facing down,
+	/// with a 30 degree inner and 45 degree outer cone.
+	pub fn new(pos: Point, power: Color) -> Self {
+		Self {
+			frame: Panel::new(pos),
+			cos_inner: f64::cos(30.0 * DEG),
+			cos_outer: f64::cos(45.0 * DEG),
+			power,
+			ies: None,
+		}
+	}
+
+	/// Turn the light so that it shines at `target`.
+	pub fn look_at(self, target: Point) -> Self {
+		let dir = target - self.frame.center;
+		self.look_dir(dir)
+	}
+
+	/// Turn the light so that it shines in direction `dir`.
+	pub fn look_dir(self, dir: Vector) -> Self {
+		Self {
+			frame: Panel::new_dir(self.frame.center, dir),
+			..self
+		}
+	}
+
+	/// Inner and outer cone angles (in radians, from the axis).
+	/// Full brightness inside the inner cone, fading out towards the outer cone.
+	pub fn with_cone(self, inner: f64, outer: f64) -> Self {
+		let outer = f64::max(inner, outer);
+		Self {
+			cos_inner: f64::cos(inner),
+			cos_outer: f64::cos(outer),
+			..self
+		}
+	}
+
+	/// Shape the beam by a photometric profile (see `parse_ies_file`), relative to its brightest direction.
+	/// The profile's nadir points along the light's axis. The cone still applies,
+	/// use an outer angle of 180 degrees to only use the profile.
+	pub fn with_ies(self, ies: IesProfile) -> Self {
+		Self { ies: Some(ies), ..self }
+	}
+
+	// Brightness in direction `dir` (unit vector), relative to the axis.
+	fn falloff(&self, dir: Vector) -> f64 {
+		let cos = self.frame.normal.dot(dir);
+		let cone = if cos >= self.cos_inner {
+			1.0
+		} else if cos <= self.cos_outer {
+			0.0
+		} else {
+			smoothstep((cos - self.cos_outer) / (self.cos_inner - self.cos_outer))
+		};
+		match &self.ies {
+			None => cone,
+			Some(ies) => {
+				let theta = f64::acos(cos.clamp(-1.0, 1.0));
+				let phi = f64::atan2(self.frame.ey.dot(dir), self.frame.ex.dot(dir));
+				cone * ies.eval(theta, phi)
+			}
+		}
+	}
+}
+
+// Smooth transition from 0 to 1, for x from 0 to 1.
+fn smoothstep(x: f64) -> f64 {
+	x * x * (3.0 - 2.0 * x)
+}
+
+impl Bounded for SpotLight {
+	fn bounds(&self) -> BoundingBox {
+		BoundingBox::empty(self.frame.center.into())
+	}
+}
+
+impl Object for SpotLight {
+	fn intersect<'s>(&'s self, _: &Ray, _: &mut HitRecord<'s>) {
+		// never intersects
+	}
+
+	fn occluded(&self, _: &Ray, _: f64) -> Occlusion {
+		Occlusion::Clear
+	}
+}
+
+impl Light for SpotLight {
+	fn sample(&self, _: &mut Rng, target: Point) -> (Point, Color) {
+		let pos = self.frame.center;
+		let delta = target - pos;
+		let dist2 = delta.len2();
+		let falloff = self.falloff(delta / dist2.sqrt());
+		if falloff <= 0.0 {
+			return (pos, Color::BLACK); // outside the beam
+		}
+		(pos, self.power * ((falloff / (4.0 * PI * dist2)) as f32))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::test_rng;
+
+	#[test]
+	fn test_sample() {
+		let light = SpotLight::new(Point(0., 0., 0.), Color::WHITE)
+			.look_dir(Vector::EX)
+			.with_cone(10.0 * DEG, 20.0 * DEG);
+		let mut trng = test_rng();
+		let mut rng = trng.for_pix((0, 0), 0);
+		let at = |rng: &mut Rng, angle: f64| light.sample(rng, Point(f64::cos(angle * DEG), f64::sin(angle * DEG), 0.)).1.r() as f64;
+
+		let full = 1.0 / (4.0 * PI);
+		assert!((at(&mut rng, 0.0) - full).abs() < 1e-6);
+		assert!((at(&mut rng, 9.0) - full).abs() < 1e-6);
+		let half = at(&mut rng, 15.0);
+		assert!(half > 0.2 * full && half < 0.8 * full, "{}", half / full);
+		assert_eq!(at(&mut rng, 21.0), 0.0);
+		assert_eq!(at(&mut rng, 180.0), 0.0);
+
+		// inverse square falloff.
+		let (_, intens) = light.sample(&mut rng, Point(10., 0., 0.));
+		assert!((intens.r() as f64 - full / 100.0).abs() < 1e-6);
+	}
+}
@@ -21,6 +21,10 @@ impl<L: Light, O: Object> Object for WithObject<L, O> {
 	fn intersect<'s>(&'s self, r: &Ray, h: &mut HitRecord<'s>) {
 		self.object.intersect(r, h)
 	}
+
+	fn occluded(&self, r: &Ray, max_t: f64) -> Occlusion {
+		self.object.occluded(r, max_t)
+	}
 }
 
 impl<L: Light, O: Object> Light for WithObject<L, O> {
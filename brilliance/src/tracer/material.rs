@@ -1,4 +1,5 @@
 use super::*;
+use std::sync::Arc;
 
 pub trait Material: Send + Sync {
 	/// Shade returns the brightness seen by Ray r which intersects
@@ -12,6 +13,19 @@ pub trait Material: Send + Sync {
 	fn occlude(&self, c: Color, pos: Point) -> Color {
 		Color::BLACK
 	}
+
+	/// Whether `occlude` always returns black.
+	/// Shadow rays then stop at the first hit with this material, in any order
+	/// (see `Object::occluded`). Materials overriding `occlude` should override this too.
+	fn is_opaque(&self) -> bool {
+		true
+	}
+
+	/// The medium inside a closed surface with this material (see `Volume`).
+	/// Rays cross such surfaces unchanged, the Scene then traces them through the medium.
+	fn medium(&self) -> Option<&dyn Medium> {
+		None
+	}
 }
 
 pub struct DynMaterial(Box<dyn Material>);
@@ -30,4 +44,63 @@ impl Material for DynMaterial {
 		let inner: &dyn Material = self.0.borrow();
 		inner.shade(s, r, h, rng, depth)
 	}
+
+	fn occlude(&self, c: Color, pos: Point) -> Color {
+		let inner: &dyn Material = self.0.borrow();
+		inner.occlude(c, pos)
+	}
+
+	fn is_opaque(&self) -> bool {
+		let inner: &dyn Material = self.0.borrow();
+		inner.is_opaque()
+	}
+
+	fn medium(&self) -> Option<&dyn Medium> {
+		let inner: &dyn Material = self.0.borrow();
+		inner.medium()
+	}
+}
+
+impl<M: Material> Material for Arc<M> {
+	fn shade(&self, s: &Scene, r: &Ray, h: &HitCoords, rng: &mut Rng, depth: u32) -> Color {
+		let inner: &M = self.borrow();
+		inner.shade(s, r, h, rng, depth)
+	}
+
+	fn occlude(&self, c: Color, pos: Point) -> Color {
+		let inner: &M = self.borrow();
+		inner.occlude(c, pos)
+	}
+
+	fn is_opaque(&self) -> bool {
+		let inner: &M = self.borrow();
+		inner.is_opaque()
+	}
+
+	fn medium(&self) -> Option<&dyn Medium> {
+		let inner: &M = self.borrow();
+		inner.medium()
+	}
+}
+
+impl Material for Arc<dyn Material> {
+	fn shade(&self, s: &Scene, r: &Ray, h: &HitCoords, rng: &mut Rng, depth: u32) -> Color {
+		let inner: &dyn Material = self.borrow();
+		inner.shade(s, r, h, rng, depth)
+	}
+
+	fn occlude(&self, c: Color, pos: Point) -> Color {
+		let inner: &dyn Material = self.borrow();
+		inner.occlude(c, pos)
+	}
+
+	fn is_opaque(&self) -> bool {
+		let inner: &dyn Material = self.borrow();
+		inner.is_opaque()
+	}
+
+	fn medium(&self) -> Option<&dyn Medium> {
+		let inner: &dyn Material = self.borrow();
+		inner.medium()
+	}
 }
@@ -1,5 +1,11 @@
 use super::*;
 
+/// Blend is a weighted sum of two materials.
+/// Both materials are shaded for every ray, so nested blends quickly become expensive.
+/// See `Mix` for a stochastic alternative with weights given by a texture.
+///
+/// Shadows are attenuated by the same weighted sum: blending opaque materials,
+/// with weights adding up to 1, stays opaque.
 pub struct Blend<A: Material, B: Material> {
 	mat_a: A,
 	weight_a: f32,
@@ -7,18 +13,60 @@ pub struct Blend<A: Material, B: Material> {
 	weight_b: f32,
 }
 
+impl<A: Material, B: Material> Blend<A, B> {
+	/// A weighted sum of two materials.
+	/// The weights should add up to at most 1 for an energy-conserving result,
+	/// unless one of the materials is emissive (e.g. `Flat`).
+	pub fn new(mat_a: A, weight_a: f32, mat_b: B, weight_b: f32) -> Self {
+		Self {
+			mat_a,
+			weight_a,
+			mat_b,
+			weight_b,
+		}
+	}
+}
+
 impl<A: Material, B: Material> Material for Blend<A, B> {
 	fn shade(&self, s: &Scene, r: &Ray, h: &HitCoords, rng: &mut Rng, depth: u32) -> Color {
 		// TODO: don't branch on integration if weight < some limit?
 		self.weight_a * self.mat_a.shade(s, r, h, rng, depth) + self.weight_b * self.mat_b.shade(s, r, h, rng, depth)
 	}
+
+	fn occlude(&self, c: Color, pos: Point) -> Color {
+		self.weight_a * self.mat_a.occlude(c, pos) + self.weight_b * self.mat_b.occlude(c, pos)
+	}
+
+	fn is_opaque(&self) -> bool {
+		self.mat_a.is_opaque() && self.mat_b.is_opaque()
+	}
 }
 
 pub fn shiny<T: Texture>(base: T, shine: f32) -> DynMaterial {
-	DynMaterial::new(Blend {
-		mat_a: Matte::new(base),
-		weight_a: 1.0 - shine,
-		mat_b: Reflective(Color::WHITE),
-		weight_b: shine,
-	})
+	DynMaterial::new(Blend::new(Matte::new(base), 1.0 - shine, Reflective(Color::WHITE), shine))
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_occlude() {
+		let pos = Point(0., 0., 0.);
+		let half = Color::new(0.5, 0.5, 0.5);
+
+		let shiny = shiny(Color::WHITE, 0.3);
+		assert_eq!(shiny.occlude(Color::WHITE, pos), Color::BLACK);
+		assert!(shiny.is_opaque());
+
+		let glass = Translucent::new(Color::WHITE, Color::BLACK);
+		let frosted = Blend::new(Matte::new(Color::WHITE), 0.5, glass, 0.5);
+		assert_eq!(frosted.occlude(Color::WHITE, pos), half);
+		assert!(!frosted.is_opaque());
+
+		// emission does not block light.
+		let glass = Translucent::new(Color::WHITE, Color::BLACK);
+		let glowing = Blend::new(glass, 1.0, Flat::new(Color::RED), 1.0);
+		assert_eq!(glowing.occlude(Color::WHITE, pos), Color::WHITE);
+	}
 }
@@ -0,0 +1,114 @@
+use super::*;
+
+/// BumpMap adds surface detail to a material by perturbing the shading normal
+/// as if the surface were displaced by a height texture (only the red channel is used).
+/// The geometry itself is not changed, so silhouettes and shadows remain smooth.
+///
+/// `scale` is the displacement, in world units, corresponding to height 1 (white).
+/// Negative values turn bumps into dents.
+/// The height should be loaded without sRGB decoding (`imageio::load_linear`).
+///
+/// Requires an object that provides tangents (`HitCoords::dpdu`, `dpdv`),
+/// like `Face`, `Quad` and `Sphere`. Otherwise, the material is left unchanged.
+///
+///     use brilliance::*;
+///     let height = Color::new(0.5, 0.5, 0.5); // typically an image
+///     let stucco = BumpMap::new(Matte::new(WHITE), height, 0.01);
+pub struct BumpMap<M: Material, T: Texture> {
+	inner: M,
+	height: T,
+	scale: f64,
+}
+
+// Texture coordinate step for finite differences of the height.
+// Small enough to resolve single texels of (bilinearly interpolated) images.
+const DELTA: f32 = 1.0 / 4096.0;
+
+impl<M: Material, T: Texture> BumpMap<M, T> {
+	pub fn new(inner: M, height: T, scale: f64) -> Self {
+		Self { inner, height, scale }
+	}
+
+	// Perturbed shading normal, or None if the surface has no tangents.
+	fn normal(&self, h: &HitCoords) -> Option<Vector> {
+		let n = h.shading_normal();
+		let dpdu: Vector = h.dpdu.into();
+		let dpdv: Vector = h.dpdv.into();
+		let area = dpdu.cross(dpdv);
+		if area.len2() == 0.0 {
+			return None;
+		}
+
+		// derivatives of the displacement along u and v.
+		let height = |du, dv| self.height.color_at(h.tex_coords + Pointf(du, dv, 0.0)).r() as f64;
+		let h0 = height(0.0, 0.0);
+		let dhdu = self.scale * (height(DELTA, 0.0) - h0) / (DELTA as f64);
+		let dhdv = self.scale * (height(0.0, DELTA) - h0) / (DELTA as f64);
+
+		// normal of the displaced surface p + height * n, i.e. the cross product of
+		// (dpdu + dhdu n) and (dpdv + dhdv n), relative to the (interpolated) shading normal.
+		let sign = if area.dot(n) < 0.0 { -1.0 } else { 1.0 };
+		let tilt = dhdv * dpdu.cross(n) + dhdu * n.cross(dpdv);
+		Some((n + (sign / area.len()) * tilt).normalized())
+	}
+}
+
+impl<M: Material, T: Texture> Material for BumpMap<M, T> {
+	fn shade(&self, s: &Scene, r: &Ray, h: &HitCoords, rng: &mut Rng, depth: u32) -> Color {
+		match self.normal(h) {
+			None => self.inner.shade(s, r, h, rng, depth),
+			Some(n) => {
+				let h = HitCoords {
+					shading_normalf: n.into(),
+					..*h
+				};
+				self.inner.shade(s, r, &h, rng, depth)
+			}
+		}
+	}
+
+	fn occlude(&self, c: Color, pos: Point) -> Color {
+		self.inner.occlude(c, pos)
+	}
+
+	fn is_opaque(&self) -> bool {
+		self.inner.is_opaque()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	// Height increasing linearly along u.
+	struct Ramp;
+
+	impl Texture for Ramp {
+		fn color_at(&self, p: Pointf) -> Color {
+			Color::new(p[0], 0.0, 0.0)
+		}
+	}
+
+	#[test]
+	fn test_normal() {
+		// plane z = 0, u along x, v along y, 2 units per texture
+		let mut h = HitCoords::background(Vectorf::EZ);
+		h.tex_coords = Pointf(0.5, 0.5, 0.0);
+		h.dpdu = Vectorf(2.0, 0.0, 0.0);
+		h.dpdv = Vectorf(0.0, 2.0, 0.0);
+
+		// slope 1: height rises by 2 over 2 units.
+		let m = BumpMap::new(Flat::new(Color::BLACK), Ramp, 2.0);
+		let n = m.normal(&h).unwrap();
+		assert!((n - Vector(-1., 0., 1.).normalized()).len() < 1e-3);
+
+		// same, with mirrored texture coordinates.
+		h.dpdv = Vectorf(0.0, -2.0, 0.0);
+		let n = m.normal(&h).unwrap();
+		assert!((n - Vector(-1., 0., 1.).normalized()).len() < 1e-3);
+
+		// no tangents
+		h.dpdu = Vectorf::ZERO;
+		assert!(m.normal(&h).is_none());
+	}
+}
@@ -0,0 +1,246 @@
+use super::internal::*;
+use super::*;
+
+/// A rough metal, modeled as a GGX (Trowbridge-Reitz) microfacet surface.
+/// E.g.: brushed aluminium, rough gold, tarnished copper.
+///
+/// The color follows from the metal's complex index of refraction
+/// (see the presets `ComplexIor::GOLD`, etc.) through the Fresnel equations,
+/// so it changes realistically towards grazing angles.
+///
+/// Roughness is given by a grayscale texture (only the red channel is used),
+/// 0 being a perfect mirror and 1 very rough.
+/// Optionally, the surface can be rougher in one direction than the other (anisotropy),
+/// like brushed metal.
+///
+/// Like `Matte`, light sources are sampled directly, and one
+/// random ray is added for the indirect illumination.
+/// Its direction is importance-sampled from the distribution of visible normals.
+///
+///     use brilliance::*;
+///     let brushed = Conductor::new(ComplexIor::ALUMINIUM, Color::new(0.3, 0.3, 0.3)).with_anisotropy(0.8, Vector::EX);
+///
+/// See Walter et al., "Microfacet Models for Refraction through Rough Surfaces" (2007),
+/// and Heitz, "Sampling the GGX Distribution of Visible Normals", JCGT (2018).
+pub struct Conductor<T: Texture> {
+	ior: ComplexIor,
+	roughness: T,
+	anisotropy: f64,
+	direction: Vector,
+}
+
+/// Complex index of refraction `eta + i k` of a metal,
+/// per color channel (red, green, blue).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ComplexIor {
+	pub eta: [f32; 3],
+	pub k: [f32; 3],
+}
+
+impl ComplexIor {
+	pub const ALUMINIUM: Self = Self {
+		eta: [1.657, 0.880, 0.521],
+		k: [9.224, 6.270, 4.837],
+	};
+	pub const COPPER: Self = Self {
+		eta: [0.200, 0.924, 1.102],
+		k: [3.912, 2.452, 2.142],
+	};
+	pub const GOLD: Self = Self {
+		eta: [0.143, 0.374, 1.442],
+		k: [3.983, 2.385, 1.603],
+	};
+	pub const SILVER: Self = Self {
+		eta: [0.155, 0.117, 0.138],
+		k: [4.828, 3.122, 2.147],
+	};
+
+	// Fraction of light reflected at incidence angle with cosine `cos_i`.
+	fn fresnel(&self, cos_i: f64) -> Color {
+		let f = |c| fresnel_conductor(cos_i, self.eta[c] as f64, self.k[c] as f64) as f32;
+		Color::new(f(0), f(1), f(2))
+	}
+}
+
+impl<T: Texture> Conductor<T> {
+	pub fn new(ior: ComplexIor, roughness: T) -> Self {
+		Self {
+			ior,
+			roughness,
+			anisotropy: 0.0,
+			direction: Vector::EX,
+		}
+	}
+
+	/// Make the surface smoother along `direction` (projected onto the surface)
+	/// than perpendicular to it, by a factor given by `anisotropy` (0: isotropic, 1: maximal),
+	/// as if brushed along `direction`.
+	pub fn with_anisotropy(self, anisotropy: f64, direction: Vector) -> Self {
+		Self {
+			anisotropy,
+			direction,
+			..self
+		}
+	}
+
+	// Roughness parameters (alpha_x, alpha_y) along the tangent and bitangent,
+	// following the Disney convention alpha = roughness^2.
+	fn alpha(&self, h: &HitCoords) -> (f64, f64) {
+		let r = self.roughness.color_at(h.tex_coords).r() as f64;
+		let alpha = f64::max(1e-3, r * r);
+		let aspect = f64::sqrt(1.0 - 0.9 * self.anisotropy);
+		(f64::max(1e-3, alpha * aspect), f64::max(1e-3, alpha / aspect))
+	}
+}
+
+impl<T: Texture> Material for Conductor<T> {
+	fn shade(&self, s: &Scene, r: &Ray, h: &HitCoords, rng: &mut Rng, depth: u32) -> Color {
+		let mut acc = Color::BLACK;
+
+		// If neccessary, flip the normal vectors to point towards the camera.
+		let mut geo_norm = h.geom_normal();
+		let mut shd_norm = h.shading_normal();
+		if geo_norm.dot(r.dir) > 0.0 {
+			geo_norm = -geo_norm;
+			shd_norm = -shd_norm;
+		}
+		if shd_norm.dot(r.dir) >= 0.0 {
+			shd_norm = geo_norm; // grazing incidence
+		}
+
+		let p = r.at(h.t) + TINY * geo_norm;
+		let frame = Frame::new(shd_norm, self.direction);
+		let ggx = Ggx::new(self.alpha(h));
+		let wo = frame.to_local(-r.dir);
+
+		// direct
+		for l in s.lights() {
+			let (lpos, intens) = l.sample(rng, p);
+			if intens == (Color::BLACK) {
+				continue; // potential shortcut for directional lights
+			}
+
+			let ldelta = lpos - p;
+			let ldir = ldelta.normalized();
+			let wi = frame.to_local(ldir);
+			if wi[2] <= 0.0 || ldir.dot(geo_norm) <= 0.0 {
+				continue; // backlit
+			}
+
+			// brdf D G F / (4 cos_o cos_i), times cos_i, relative to Matte (times pi).
+			let m = (wo + wi).normalized();
+			let f = PI * ggx.d(m) * ggx.g2(wo, wi) / (4.0 * wo[2]);
+			if f < 1e-6 {
+				continue; // outside of highlight, don't bother tracing shadow ray
+			}
+
+			let sec = Ray::new(p, ldir);
+			let intens = s.occlude(&sec, ldelta.len(), intens);
+			acc += intens * self.ior.fresnel(wo.dot(m)) * (f as f32);
+		}
+
+		// indirect
+		let (u, v) = rng.quasi_random2();
+		let m = ggx.sample_visible_normal(wo, (u as f64, v as f64));
+		let wi = reflect(-wo, m);
+		let dir = frame.to_world(wi);
+		if wi[2] > 0.0 && dir.dot(geo_norm) > 0.0 {
+			// with visible normal sampling, brdf * cos / pdf = F G2 / G1.
+			let weight = ggx.g2(wo, wi) / ggx.g1(wo);
+			let sec = Ray::new(p, dir);
+			acc += s.lightfield_indirect(&sec, rng, depth + 1) * self.ior.fresnel(wo.dot(m)) * (weight as f32);
+		}
+
+		acc
+	}
+}
+
+// Fraction of unpolarized light reflected by a conductor with complex index of refraction eta + i k,
+// at incidence angle with cosine `cos_i`.
+//
+// See https://seblagarde.wordpress.com/2013/04/29/memo-on-fresnel-equations/
+fn fresnel_conductor(cos_i: f64, eta: f64, k: f64) -> f64 {
+	let cos2 = cos_i * cos_i;
+	let sin2 = 1.0 - cos2;
+	let t0 = eta * eta - k * k - sin2;
+	let a2b2 = f64::sqrt(t0 * t0 + 4.0 * eta * eta * k * k);
+	let t1 = a2b2 + cos2;
+	let a = f64::sqrt(0.5 * (a2b2 + t0));
+	let t2 = 2.0 * cos_i * a;
+	let rs = (t1 - t2) / (t1 + t2);
+	let t3 = cos2 * a2b2 + sin2 * sin2;
+	let t4 = t2 * sin2;
+	let rp = rs * (t3 - t4) / (t3 + t4);
+	(rs + rp) / 2.0
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_fresnel() {
+		// normal incidence: ((eta-1)^2 + k^2) / ((eta+1)^2 + k^2)
+		let (eta, k) = (0.2, 3.9);
+		let want = ((eta - 1.0) * (eta - 1.0) + k * k) / ((eta + 1.0) * (eta + 1.0) + k * k);
+		assert!((fresnel_conductor(1.0, eta, k) - want).abs() < 1e-9);
+		// grazing incidence
+		assert!((fresnel_conductor(0.0, eta, k) - 1.0).abs() < 1e-9);
+
+		// gold is yellow, silver is white-ish.
+		let gold = ComplexIor::GOLD.fresnel(1.0);
+		assert!(gold.r() > 0.9 && gold.b() < 0.5);
+		let silver = ComplexIor::SILVER.fresnel(1.0);
+		assert!(silver.b() > 0.9);
+	}
+
+	// With a perfect reflector (F = 1), visible normal sampling must not create energy,
+	// and lose little of it for smooth surfaces (multiple scattering between
+	// microfacets is not modeled, so very rough surfaces lose up to 70%).
+	#[test]
+	fn test_energy() {
+		for &(ax, ay) in &[(0.01, 0.01), (0.3, 0.3), (0.05, 0.5), (1.0, 1.0)] {
+			let ggx = Ggx::new((ax, ay));
+			for &angle in &[0.0, 45.0, 80.0] {
+				let wo = Vector(f64::sin(angle * DEG), 0.0, f64::cos(angle * DEG));
+				let n = 100;
+				let mut sum = 0.0;
+				for i in 0..n {
+					for j in 0..n {
+						let uv = ((i as f64 + 0.5) / n as f64, (j as f64 + 0.5) / n as f64);
+						let m = ggx.sample_visible_normal(wo, uv);
+						assert!(m[2] >= 0.0 && m.dot(wo) >= -1e-9);
+						let wi = reflect(-wo, m);
+						if wi[2] > 0.0 {
+							sum += ggx.g2(wo, wi) / ggx.g1(wo);
+						}
+					}
+				}
+				let albedo = sum / (n * n) as f64;
+				assert!(albedo <= 1.0);
+				if f64::max(ax, ay) <= 0.3 && angle < 60.0 {
+					assert!(albedo > 0.8);
+				}
+			}
+		}
+	}
+
+	// The NDF, projected onto the macro surface, integrates to 1.
+	#[test]
+	fn test_ndf() {
+		let ggx = Ggx::new((0.2, 0.4));
+		let n = 400;
+		let mut sum = 0.0;
+		for i in 0..n {
+			for j in 0..n {
+				// uniform in (cos theta, phi)
+				let cos_t = (i as f64 + 0.5) / n as f64;
+				let phi = 2.0 * PI * (j as f64 + 0.5) / n as f64;
+				let sin_t = f64::sqrt(1.0 - cos_t * cos_t);
+				let m = Vector(sin_t * f64::cos(phi), sin_t * f64::sin(phi), cos_t);
+				sum += ggx.d(m) * cos_t * 2.0 * PI / (n * n) as f64;
+			}
+		}
+		assert!((sum - 1.0).abs() < 1e-2);
+	}
+}
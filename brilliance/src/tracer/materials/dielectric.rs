@@ -0,0 +1,203 @@
+use super::internal::*;
+use super::*;
+
+/// A transparent material that refracts light, like glass or water.
+///
+/// Light is reflected or refracted (following Snell's law) with probabilities given
+/// by the exact Fresnel equations, including total internal reflection.
+/// One of both is chosen at random for each ray, so the number of rays
+/// does not grow with recursion depth.
+///
+/// The inside of the object is the side opposite to the geometric normal,
+/// so the shape should be closed and consistently oriented.
+/// Light traveling through the inside is absorbed following the Beer-Lambert law.
+///
+/// Shadow rays are blocked: light refracted towards a surface (caustics)
+/// only arrives there through indirect rays.
+///
+///     use brilliance::*;
+///     let glass = Dielectric::new(1.5).with_absorption(Color::new(0.1, 0.0, 0.1));
+pub struct Dielectric {
+	ior: f64,
+	absorption: Color,
+}
+
+impl Dielectric {
+	/// A clear dielectric with index of refraction `ior`
+	/// (relative to the surrounding medium). E.g. 1.33 for water, 1.5 for glass.
+	pub fn new(ior: f64) -> Self {
+		Self {
+			ior,
+			absorption: Color::BLACK,
+		}
+	}
+
+	/// Absorption coefficient per unit length, per color channel.
+	/// Light traveling a distance `d` inside is attenuated by `exp(-absorption * d)`.
+	pub fn with_absorption(self, absorption: Color) -> Self {
+		Self { absorption, ..self }
+	}
+}
+
+impl Material for Dielectric {
+	fn shade(&self, s: &Scene, r: &Ray, h: &HitCoords, rng: &mut Rng, depth: u32) -> Color {
+		// Normals pointing towards the side the ray comes from.
+		// Rays hitting the back side are leaving the medium.
+		let mut geo_norm = h.geom_normal();
+		let mut shd_norm = h.shading_normal();
+		let entering = geo_norm.dot(r.dir) < 0.0;
+		if !entering {
+			geo_norm = -geo_norm;
+			shd_norm = -shd_norm;
+		}
+		// use the geometric normal in case the shading normal points away
+		// (as can happen under grazing incidence).
+		if shd_norm.dot(r.dir) >= 0.0 {
+			shd_norm = geo_norm;
+		}
+
+		// light reaching us from inside the medium was absorbed along the way.
+		let transmittance = if entering {
+			Color::WHITE
+		} else {
+			self.absorption.apply(|a| (-a * (h.t as f32)).exp())
+		};
+
+		let eta = if entering { 1.0 / self.ior } else { self.ior };
+		let cos_i = -r.dir.dot(shd_norm);
+		let p = r.at(h.t);
+
+		let sec = match refract(r.dir, shd_norm, eta, cos_i) {
+			Some(dir) if (rng.random() as f64) >= fresnel_dielectric(cos_i, eta) => Ray::new(p - TINY * geo_norm, dir),
+			_ => Ray::new(p + TINY * geo_norm, reflect(r.dir, shd_norm)),
+		};
+
+		transmittance * s.lightfield(&sec, rng, depth + 1)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::{default_scene, test_rng};
+
+	// White background, no lights.
+	fn scene(objects: Vec<DynObj>) -> Scene {
+		Scene {
+			background: DynMaterial::new(Flat::new(Color::WHITE)),
+			objects: QTree::new(objects),
+			lights: vec![],
+			max_recursion_depth: 50,
+			..default_scene()
+		}
+	}
+
+	#[test]
+	fn test_energy() {
+		// Whether reflected or refracted, all light coming through a glass sphere
+		// comes from the background in the end, only attenuated by absorption.
+		let a = 0.2f32;
+		let glass = |absorption| {
+			scene(vec![DynObj::new(
+				Sphere::new(Point(0., 0., 0.), 2.0).paint(Dielectric::new(1.5).with_absorption(absorption)),
+			)])
+		};
+		let clear = glass(Color::BLACK);
+		let tinted = glass(Color::new(a, a, a));
+		let mut trng = test_rng();
+		let mut rng = trng.for_pix((0, 0), 0);
+		let mut passes = [0; 3];
+		for i in 0..200 {
+			let x = (i % 10) as f64 / 10.0;
+			let r = Ray::new(Point(x, 0., 5.), -Vector::EZ);
+			assert_eq!(clear.lightfield(&r, &mut rng, 0), Color::WHITE);
+
+			// through the center: each pass through the sphere (length 2) attenuates by exp(-2a).
+			let r = Ray::new(Point(0., 0., 5.), -Vector::EZ);
+			let c = tinted.lightfield(&r, &mut rng, 0).r();
+			let k = f32::round(c.ln() / (-2.0 * a));
+			assert!((c - f32::exp(-2.0 * a * k)).abs() < 1e-4, "{}", c);
+			passes[usize::min(k as usize, 2)] += 1;
+		}
+		// mostly straight through, sometimes reflected off the front.
+		assert!(passes[1] > 150);
+		assert!(passes[0] > 0);
+	}
+
+	#[test]
+	fn test_total_internal_reflection() {
+		// Background white below the surface z = 0, black above.
+		struct Below;
+		impl Material for Below {
+			fn shade(&self, _: &Scene, r: &Ray, _: &HitCoords, _: &mut Rng, _: u32) -> Color {
+				if r.dir[2] < 0.0 {
+					Color::WHITE
+				} else {
+					Color::BLACK
+				}
+			}
+		}
+		let scene = Scene {
+			background: DynMaterial::new(Below),
+			..scene(vec![])
+		};
+
+		// Ray reaching the surface from inside (below), at angle theta from the normal.
+		let shade = |theta: f64, rng: &mut Rng| {
+			let r = Ray::new(Point(-theta.sin(), 0., -theta.cos()), Vector(theta.sin(), 0., theta.cos()));
+			let mut h = HitCoords::background(r.dir.into());
+			h.t = 1.0;
+			h.geom_normalf = Vectorf::EZ;
+			h.shading_normalf = Vectorf::EZ;
+			Dielectric::new(1.5).shade(&scene, &r, &h, rng, 0)
+		};
+
+		// the critical angle is asin(1/1.5) = 41.8 degrees.
+		let mut trng = test_rng();
+		let mut rng = trng.for_pix((0, 0), 0);
+		let mut escaped = 0;
+		for _ in 0..100 {
+			assert_eq!(shade(60.0 * DEG, &mut rng), Color::WHITE);
+			if shade(30.0 * DEG, &mut rng) == Color::BLACK {
+				escaped += 1;
+			}
+		}
+		assert!(escaped > 50);
+	}
+
+	#[test]
+	fn test_fresnel() {
+		// normal incidence: ((n1 - n2) / (n1 + n2))^2, both ways.
+		assert!((fresnel_dielectric(1.0, 1.0 / 1.5) - 0.04).abs() < 1e-9);
+		assert!((fresnel_dielectric(1.0, 1.5) - 0.04).abs() < 1e-9);
+
+		// Brewster's angle: p-polarized light is fully transmitted.
+		let brewster = f64::atan(1.5);
+		assert!((fresnel_dielectric(brewster.cos(), 1.0 / 1.5) - 0.5 * 0.1479).abs() < 1e-3);
+
+		// grazing incidence, and beyond the critical angle.
+		assert!((fresnel_dielectric(0.0, 1.0 / 1.5) - 1.0).abs() < 1e-9);
+		let critical = f64::asin(1.0 / 1.5);
+		assert_eq!(fresnel_dielectric((critical + 0.01).cos(), 1.5), 1.0);
+		assert!(fresnel_dielectric((critical - 0.01).cos(), 1.5) < 1.0);
+	}
+
+	#[test]
+	fn test_refract() {
+		// Snell: sin(theta_t) = eta * sin(theta_i).
+		let n = Vector::EY;
+		let theta_i = 30.0 * DEG;
+		let v = Vector(theta_i.sin(), -theta_i.cos(), 0.0);
+		let eta = 1.0 / 1.5;
+		let t = refract(v, n, eta, -v.dot(n)).unwrap();
+		assert!((t.len() - 1.0).abs() < 1e-9);
+		assert!((t[0] - eta * theta_i.sin()).abs() < 1e-9);
+		assert!(t[1] < 0.0);
+
+		// straight through at normal incidence
+		assert!((refract(-n, n, eta, 1.0).unwrap() + n).len() < 1e-9);
+
+		// total internal reflection
+		assert!(refract(v, n, 1.5 * 1.5, -v.dot(n)).is_none());
+	}
+}
@@ -0,0 +1,51 @@
+use super::internal::*;
+use super::*;
+
+/// Dissolve makes a material partially transparent,
+/// like the `d` (dissolve) and `map_d` statements of Wavefront MTL files.
+///
+/// The opacity in each point is `d` times the mask texture
+/// (per color channel, so colored masks tint the transmitted light).
+/// Opacity 1 shows the inner material, opacity 0 lets rays pass straight through.
+///
+/// Shadows are attenuated by `d` alone, as `Material::occlude`
+/// does not know the texture coordinates needed to look up the mask.
+/// For cut-outs that also cast cut-out shadows (e.g. leaves), use `WithAlpha` instead.
+pub struct Dissolve<M: Material, T: Texture> {
+	inner: M,
+	d: f32,
+	mask: T,
+}
+
+impl<M: Material, T: Texture> Dissolve<M, T> {
+	pub fn new(inner: M, d: f32, mask: T) -> Self {
+		Self { inner, d, mask }
+	}
+}
+
+impl<M: Material, T: Texture> Material for Dissolve<M, T> {
+	fn shade(&self, s: &Scene, r: &Ray, h: &HitCoords, rng: &mut Rng, depth: u32) -> Color {
+		let opacity = self.d * self.mask.color_filtered(h.tex_coords, &h.footprint);
+		let mut acc = Color::BLACK;
+
+		if opacity != Color::BLACK {
+			acc += opacity * self.inner.shade(s, r, h, rng, depth);
+		}
+
+		let transp = opacity.apply(|x| 1.0 - x);
+		if transp != Color::BLACK {
+			let transm = Ray::new(r.at(h.t + 2.0 * TINY), r.dir);
+			acc += transp * s.lightfield(&transm, rng, depth); // no need to increase depth, ray direction unchanged
+		}
+
+		acc
+	}
+
+	fn occlude(&self, c: Color, pos: Point) -> Color {
+		self.d * self.inner.occlude(c, pos) + (1.0 - self.d) * c
+	}
+
+	fn is_opaque(&self) -> bool {
+		self.d >= 1.0 && self.inner.is_opaque()
+	}
+}
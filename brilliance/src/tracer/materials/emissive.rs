@@ -0,0 +1,38 @@
+use super::*;
+
+/// Emissive is a material for light-emitting surfaces, e.g. light panels, screens, neon tubes.
+/// The texture gives the emitted radiance (brightness seen by the camera).
+///
+/// Unlike `Flat`, light is only emitted from the front side
+/// (the side the geometric normal points to). The back side is black.
+///
+/// Surfaces only light up their surroundings through random (indirect) rays,
+/// which is noisy for small, bright emitters. For those, register the mesh as a light source
+/// with `MeshLight`, which samples it directly.
+///
+///     use brilliance::*;
+///     let panel = Emissive::new(WHITE.ev(2.0));
+pub struct Emissive<T: Texture> {
+	tex: T,
+}
+
+impl<T: Texture> Emissive<T> {
+	pub fn new(tex: T) -> Self {
+		Self { tex }
+	}
+
+	/// Radiance emitted at given texture coordinates, towards the front side.
+	pub fn radiance(&self, tex_coords: Pointf) -> Color {
+		self.tex.color_at(tex_coords)
+	}
+}
+
+impl<T: Texture> Material for Emissive<T> {
+	fn shade(&self, _: &Scene, r: &Ray, h: &HitCoords, _: &mut Rng, _: u32) -> Color {
+		if h.geom_normal().dot(r.dir) < 0.0 {
+			self.tex.color_filtered(h.tex_coords, &h.footprint)
+		} else {
+			Color::BLACK
+		}
+	}
+}
@@ -16,6 +16,6 @@ impl<T: Texture> Flat<T> {
 
 impl<T: Texture> Material for Flat<T> {
 	fn shade(&self, _: &Scene, _: &Ray, h: &HitCoords, _: &mut Rng, _: u32) -> Color {
-		self.tex.color_at(h.tex_coords)
+		self.tex.color_filtered(h.tex_coords, &h.footprint)
 	}
 }
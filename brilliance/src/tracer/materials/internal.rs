@@ -13,3 +13,149 @@ pub fn shade_normal(r: &Ray, n: &Vector) -> Color {
 }
 
 pub const TINY: f64 = 1.0 / (1024.0 * 1024.0);
+
+// reflect v along normal vector n.
+//      n
+//  v   |   reflected
+//   \  |  /
+//    \θ|θ/
+//     \|/
+//  --------- surface.
+//
+// See https://en.wikipedia.org/wiki/Ray_tracing_(graphics)#Example
+pub fn reflect(v: Vector, n: Vector) -> Vector {
+	v - 2.0 * v.dot(n) * n
+}
+
+// Direction of a ray with direction `v` refracted by a surface with normal `n`
+// (pointing towards the incident side), `cos_i = -v.dot(n)`,
+// and relative index of refraction `eta` = n_incident / n_transmitted.
+// None in case of total internal reflection.
+//
+// See https://en.wikipedia.org/wiki/Snell%27s_law#Vector_form
+pub fn refract(v: Vector, n: Vector, eta: f64, cos_i: f64) -> Option<Vector> {
+	let sin2_t = eta * eta * (1.0 - cos_i * cos_i);
+	if sin2_t > 1.0 {
+		return None;
+	}
+	let cos_t = f64::sqrt(1.0 - sin2_t);
+	Some(eta * v + (eta * cos_i - cos_t) * n)
+}
+
+// Fraction of unpolarized light reflected (rather than refracted)
+// at incidence angle with cosine `cos_i`, for relative index of refraction `eta`.
+// 1 in case of total internal reflection.
+//
+// See https://en.wikipedia.org/wiki/Fresnel_equations#Power_(intensity)_reflection_and_transmission_coefficients
+pub fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+	let sin2_t = eta * eta * (1.0 - cos_i * cos_i);
+	if sin2_t >= 1.0 {
+		return 1.0;
+	}
+	let cos_t = f64::sqrt(1.0 - sin2_t);
+	let r_s = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+	let r_p = (eta * cos_t - cos_i) / (eta * cos_t + cos_i);
+	(r_s * r_s + r_p * r_p) / 2.0
+}
+
+// Schlick's approximation of the Fresnel reflectance,
+// for reflectance f0 at normal incidence.
+pub fn schlick(f0: f64, cos_i: f64) -> f64 {
+	f0 + (1.0 - f0) * schlick_weight(cos_i)
+}
+
+// (1 - cos)^5, the angular dependence of Schlick's approximation.
+pub fn schlick_weight(cos_i: f64) -> f64 {
+	let x = f64::max(0.0, 1.0 - cos_i);
+	let x2 = x * x;
+	x2 * x2 * x
+}
+
+// Orthonormal tangent frame, normal along z.
+pub struct Frame {
+	pub t: Vector,
+	pub b: Vector,
+	pub n: Vector,
+}
+
+impl Frame {
+	// Frame with normal n, and tangent closest to direction.
+	pub fn new(n: Vector, direction: Vector) -> Self {
+		let t = direction - n.dot(direction) * n;
+		let t = if t.len2() > 1e-12 { t.normalized() } else { make_basis(n)[0] };
+		Self { t, b: n.cross(t), n }
+	}
+
+	pub fn to_local(&self, v: Vector) -> Vector {
+		Vector(v.dot(self.t), v.dot(self.b), v.dot(self.n))
+	}
+
+	pub fn to_world(&self, v: Vector) -> Vector {
+		v[0] * self.t + v[1] * self.b + v[2] * self.n
+	}
+}
+
+// Anisotropic GGX microfacet distribution, in a local frame with the normal along z.
+pub struct Ggx {
+	pub ax: f64,
+	pub ay: f64,
+}
+
+impl Ggx {
+	pub fn new((ax, ay): (f64, f64)) -> Self {
+		Self { ax, ay }
+	}
+
+	// Normal distribution function.
+	pub fn d(&self, m: Vector) -> f64 {
+		if m[2] <= 0.0 {
+			return 0.0;
+		}
+		let (x, y, z) = (m[0] / self.ax, m[1] / self.ay, m[2]);
+		let s = x * x + y * y + z * z;
+		1.0 / (PI * self.ax * self.ay * s * s)
+	}
+
+	// Smith's auxiliary function.
+	pub fn lambda(&self, w: Vector) -> f64 {
+		let (x, y, z) = (self.ax * w[0], self.ay * w[1], w[2]);
+		(-1.0 + f64::sqrt(1.0 + (x * x + y * y) / (z * z))) / 2.0
+	}
+
+	// Masking function.
+	pub fn g1(&self, w: Vector) -> f64 {
+		1.0 / (1.0 + self.lambda(w))
+	}
+
+	// Height-correlated masking-shadowing function.
+	pub fn g2(&self, wo: Vector, wi: Vector) -> f64 {
+		1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+	}
+
+	// Sample a microfacet normal from the distribution of normals visible from wo,
+	// for random numbers (u1, u2) in the unit square. After Heitz (2018).
+	pub fn sample_visible_normal(&self, wo: Vector, (u1, u2): (f64, f64)) -> Vector {
+		// stretch to the hemisphere configuration
+		let vh = Vector(self.ax * wo[0], self.ay * wo[1], wo[2]).normalized();
+
+		// orthonormal basis around vh
+		let lensq = vh[0] * vh[0] + vh[1] * vh[1];
+		let t1 = if lensq > 0.0 {
+			Vector(-vh[1], vh[0], 0.0) / f64::sqrt(lensq)
+		} else {
+			Vector::EX
+		};
+		let t2 = vh.cross(t1);
+
+		// uniform point on the projected (half) disk
+		let r = f64::sqrt(u1);
+		let phi = 2.0 * PI * u2;
+		let p1 = r * f64::cos(phi);
+		let s = 0.5 * (1.0 + vh[2]);
+		let p2 = (1.0 - s) * f64::sqrt(1.0 - p1 * p1) + s * r * f64::sin(phi);
+
+		// reproject onto the hemisphere, and unstretch
+		let nh = p1 * t1 + p2 * t2 + f64::sqrt(f64::max(0.0, 1.0 - p1 * p1 - p2 * p2)) * vh;
+		Vector(self.ax * nh[0], self.ay * nh[1], f64::max(0.0, nh[2])).normalized()
+	}
+}
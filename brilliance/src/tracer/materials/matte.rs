@@ -98,6 +98,6 @@ impl<T: Texture> Material for Matte<T> {
 		let sec = Ray::new(p, dir);
 		acc += s.lightfield_indirect(&sec, rng, depth + 1);
 
-		acc * self.tex.color_at(h.tex_coords)
+		acc * self.tex.color_filtered(h.tex_coords, &h.footprint)
 	}
 }
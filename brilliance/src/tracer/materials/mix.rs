@@ -0,0 +1,141 @@
+use super::*;
+
+/// Mix shows material `a` where the mask texture is black,
+/// material `b` where it is white, and a mixture in between.
+/// E.g. rust spots on painted metal, decals, worn paint.
+///
+/// Unlike `Blend`, only one of both materials is shaded per ray,
+/// chosen at random with a probability given by the mask.
+/// So mixing costs no more than shading a single material,
+/// also when nested, at the price of some extra noise.
+///
+/// The mask may be colored (e.g. a photo), in which case each color channel
+/// mixes separately, and the result is rescaled to remain correct on average.
+///
+///     use brilliance::*;
+///     let rusty = Mix::new(Matte::new(WHITE), Matte::new(Color::new(0.4, 0.2, 0.1)), Color::new(0.3, 0.3, 0.3));
+///
+/// Limitation: shadows are attenuated by both materials, weighted by the mask's average
+/// over the unit square of texture coordinates, as `Material::occlude` does not know
+/// the texture coordinates needed to look up the mask. So uniform masks cast exact shadows,
+/// but e.g. a pattern of glass and metal casts a uniform, half-transparent shadow.
+/// For cut-outs with cut-out shadows, use `WithAlpha` instead.
+pub struct Mix<A: Material, B: Material, T: Texture> {
+	mat_a: A,
+	mat_b: B,
+	mask: T,
+	mean: Color, // average of the mask, for occlude
+}
+
+impl<A: Material, B: Material, T: Texture> Mix<A, B, T> {
+	pub fn new(mat_a: A, mat_b: B, mask: T) -> Self {
+		let mean = mask_mean(&mask);
+		Self { mat_a, mat_b, mask, mean }
+	}
+}
+
+// Average of a mask (clamped to 0..1) over the unit square, on a grid of sample points.
+fn mask_mean<T: Texture>(mask: &T) -> Color {
+	const N: u32 = 16;
+	let mut acc = Color::BLACK;
+	for i in 0..N {
+		for j in 0..N {
+			let p = Pointf((i as f32 + 0.5) / N as f32, (j as f32 + 0.5) / N as f32, 0.0);
+			acc += mask.color_at(p).apply(|x| x.clamp(0.0, 1.0));
+		}
+	}
+	acc / (N * N) as f32
+}
+
+impl<A: Material, B: Material, T: Texture> Material for Mix<A, B, T> {
+	fn shade(&self, s: &Scene, r: &Ray, h: &HitCoords, rng: &mut Rng, depth: u32) -> Color {
+		let w = self.mask.color_filtered(h.tex_coords, &h.footprint).apply(|x| x.clamp(0.0, 1.0));
+
+		// probability of shading b. The weights of the chosen material
+		// are divided by it, which cancels out for gray masks.
+		let p = w.average();
+		if p == 0.0 {
+			return self.mat_a.shade(s, r, h, rng, depth);
+		}
+		if p == 1.0 {
+			return self.mat_b.shade(s, r, h, rng, depth);
+		}
+
+		if rng.random() < p {
+			(w * (1.0 / p)) * self.mat_b.shade(s, r, h, rng, depth)
+		} else {
+			(w.apply(|x| 1.0 - x) * (1.0 / (1.0 - p))) * self.mat_a.shade(s, r, h, rng, depth)
+		}
+	}
+
+	fn occlude(&self, c: Color, pos: Point) -> Color {
+		let w = self.mean;
+		let mut acc = Color::BLACK;
+		if w != Color::WHITE {
+			acc += w.apply(|x| 1.0 - x) * self.mat_a.occlude(c, pos);
+		}
+		if w != Color::BLACK {
+			acc += w * self.mat_b.occlude(c, pos);
+		}
+		acc
+	}
+
+	fn is_opaque(&self) -> bool {
+		(self.mean == Color::WHITE || self.mat_a.is_opaque()) && (self.mean == Color::BLACK || self.mat_b.is_opaque())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::{default_scene, test_rng};
+
+	// Average color over n samples.
+	fn mean<M: Material>(m: &M, n: u32) -> Color {
+		let scene = default_scene();
+		let r = Ray::new(Point(0., 0., 1.), -Vector::EZ);
+		let h = HitCoords::background(r.dir.into());
+		let mut trng = test_rng();
+		let mut acc = Color::BLACK;
+		for i in 0..n {
+			acc += m.shade(&scene, &r, &h, &mut trng.for_pix((0, 0), i), 0);
+		}
+		acc / n as f32
+	}
+
+	#[test]
+	fn test_shade() {
+		let mix = |mask| Mix::new(Flat::new(Color::WHITE), Flat::new(Color::new(2.0, 2.0, 2.0)), mask);
+
+		// black and white masks: a single material.
+		assert_eq!(mean(&mix(Color::BLACK), 10), Color::WHITE);
+		assert_eq!(mean(&mix(Color::WHITE), 10), Color::new(2.0, 2.0, 2.0));
+
+		// colored mask: each channel mixes separately, on average.
+		let got = mean(&mix(Color::new(0.2, 0.5, 0.8)), 10000);
+		let want = Color::new(1.2, 1.5, 1.8);
+		for i in 0..3 {
+			assert!((got[i] - want[i]).abs() < 0.02, "{:?}", got);
+		}
+	}
+
+	#[test]
+	fn test_occlude() {
+		let half = Color::new(0.5, 0.5, 0.5);
+		let pos = Point(0., 0., 0.);
+		let glass = || Translucent::new(Color::WHITE, Color::BLACK);
+
+		// black mask: only material a casts shadows.
+		let m = Mix::new(Matte::new(Color::WHITE), glass(), Color::BLACK);
+		assert_eq!(m.occlude(Color::WHITE, pos), Color::BLACK);
+		assert!(m.is_opaque());
+		let m = Mix::new(glass(), Matte::new(Color::WHITE), Color::BLACK);
+		assert_eq!(m.occlude(Color::WHITE, pos), Color::WHITE);
+		assert!(!m.is_opaque());
+
+		// gray mask: weighted.
+		let m = Mix::new(Matte::new(Color::WHITE), glass(), half);
+		assert_eq!(m.occlude(Color::WHITE, pos), half);
+		assert!(!m.is_opaque());
+	}
+}
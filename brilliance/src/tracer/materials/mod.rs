@@ -1,20 +1,38 @@
 pub mod blend;
+pub mod bumpmap;
+pub mod conductor;
+pub mod dielectric;
+pub mod dissolve;
+pub mod emissive;
 pub mod flat;
 pub mod geomnormal;
 pub mod matte;
+pub mod mix;
+pub mod normalmap;
+pub mod principled;
 pub mod reflective;
 pub mod shadingnormal;
 pub mod specular;
 pub mod translucent;
+pub mod volume;
 
 pub use blend::*;
+pub use bumpmap::*;
+pub use conductor::*;
+pub use dielectric::*;
+pub use dissolve::*;
+pub use emissive::*;
 pub use flat::*;
 pub use geomnormal::*;
 pub use matte::*;
+pub use mix::*;
+pub use normalmap::*;
+pub use principled::*;
 pub use reflective::*;
 pub use shadingnormal::*;
 pub use specular::*;
 pub use translucent::*;
+pub use volume::*;
 
 pub mod internal; // not re-exported
 
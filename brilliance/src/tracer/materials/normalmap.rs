@@ -0,0 +1,95 @@
+use super::internal::*;
+use super::*;
+
+/// NormalMap adds surface detail to a material by perturbing the shading normal
+/// according to a tangent-space normal map (the typical blue-ish images).
+///
+/// Each texel's red, green and blue channels encode the normal's x, y, z components
+/// (mapped from -1..1 to 0..1), relative to the tangent frame given by the increasing
+/// u (x) and v (y) texture coordinates, and the interpolated normal (z).
+/// This is the OpenGL convention (green pointing "up" in the image).
+/// The image should be loaded without sRGB decoding (`imageio::load_linear`).
+///
+/// Requires an object that provides tangents (`HitCoords::dpdu`, `dpdv`),
+/// like `Face`, `Quad` and `Sphere`. Otherwise, the normal map's orientation
+/// around the normal is arbitrary.
+///
+///     use brilliance::*;
+///     let flat = Color::new(0.5, 0.5, 1.0); // normal map without detail
+///     let tiles = NormalMap::new(Matte::new(WHITE), flat).with_strength(0.5);
+pub struct NormalMap<M: Material, T: Texture> {
+	inner: M,
+	map: T,
+	strength: f64,
+}
+
+impl<M: Material, T: Texture> NormalMap<M, T> {
+	pub fn new(inner: M, map: T) -> Self {
+		Self { inner, map, strength: 1.0 }
+	}
+
+	/// Scale the tilt of the normals, 0 disabling the normal map and 1 being as-is.
+	pub fn with_strength(self, strength: f64) -> Self {
+		Self { strength, ..self }
+	}
+
+	// Perturbed shading normal.
+	fn normal(&self, h: &HitCoords) -> Vector {
+		let n = h.shading_normal();
+		let dpdu: Vector = h.dpdu.into();
+		let mut frame = Frame::new(n, dpdu);
+		if n.cross(dpdu).dot(h.dpdv.into()) < 0.0 {
+			frame.b = -frame.b; // mirrored texture coordinates
+		}
+
+		let c = self.map.color_at(h.tex_coords);
+		let x = self.strength * (2.0 * c.r() as f64 - 1.0);
+		let y = self.strength * (2.0 * c.g() as f64 - 1.0);
+		let z = f64::max(1e-3, 2.0 * c.b() as f64 - 1.0);
+		frame.to_world(Vector(x, y, z)).normalized()
+	}
+}
+
+impl<M: Material, T: Texture> Material for NormalMap<M, T> {
+	fn shade(&self, s: &Scene, r: &Ray, h: &HitCoords, rng: &mut Rng, depth: u32) -> Color {
+		let h = HitCoords {
+			shading_normalf: self.normal(h).into(),
+			..*h
+		};
+		self.inner.shade(s, r, &h, rng, depth)
+	}
+
+	fn occlude(&self, c: Color, pos: Point) -> Color {
+		self.inner.occlude(c, pos)
+	}
+
+	fn is_opaque(&self) -> bool {
+		self.inner.is_opaque()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_normal() {
+		let mut h = HitCoords::background(Vectorf::EZ);
+		h.dpdu = Vectorf::EX;
+		h.dpdv = Vectorf::EY;
+
+		// neutral color leaves the normal unchanged
+		let m = NormalMap::new(Flat::new(Color::BLACK), Color::new(0.5, 0.5, 1.0));
+		assert!((m.normal(&h) - Vector::EZ).len() < 1e-6);
+
+		// tilted towards +u
+		let m = NormalMap::new(Flat::new(Color::BLACK), Color::new(1.0, 0.5, 0.5));
+		assert!((m.normal(&h) - Vector(1., 0., 0.).normalized()).len() < 1e-2);
+
+		// tilted towards +v, also with mirrored texture coordinates.
+		let m = NormalMap::new(Flat::new(Color::BLACK), Color::new(0.5, 1.0, 0.5));
+		assert!(m.normal(&h)[1] > 0.99);
+		h.dpdv = -Vectorf::EY;
+		assert!(m.normal(&h)[1] < -0.99);
+	}
+}
@@ -0,0 +1,348 @@
+use super::internal::*;
+use super::*;
+use std::sync::Arc;
+
+/// A physically based "uber" material, loosely following
+/// Disney's principled BRDF and Blender's Principled BSDF.
+/// Every parameter can be driven by a texture
+/// (for scalar parameters, only the red channel is used).
+///
+///  * base color: diffuse color, or reflectance of metals.
+///  * metallic: 0 for dielectrics (plastic, wood, ...), 1 for metals.
+///  * roughness: 0 for mirror-like, 1 for very rough reflections.
+///  * specular: reflectance of dielectrics at normal incidence, 0.5 corresponding to 4%.
+///  * clearcoat: strength of a glossy layer on top (e.g. car paint, varnish).
+///  * sheen: grazing-angle retro-reflection for cloth-like materials.
+///  * transmission: 0 for opaque, 1 for glass-like dielectrics (tinted by the base color).
+///
+/// The material is a stack of layers. Each layer reflects a fraction of the light
+/// given by its (Schlick) Fresnel term, and passes the remainder on to the layers below,
+/// so that no more light is reflected than received, at any view angle:
+///
+/// ```text
+/// clearcoat
+/// metal | dielectric specular
+///         diffuse (+ sheen) | transmission
+/// ```
+///
+/// Like `Matte`, light sources are sampled directly, and one random ray
+/// is added for the indirect illumination. The layer it samples is chosen
+/// at random, so the number of rays does not grow with the number of layers.
+///
+/// Shadow rays are blocked, whatever the transmission: as with `Dielectric`,
+/// light passing through only arrives behind the surface through indirect rays.
+///
+///     use brilliance::*;
+///     let car_paint = Principled::new(Color::new(0.6, 0.0, 0.0)).roughness(Color::new(0.4, 0.4, 0.4)).clearcoat(WHITE);
+pub struct Principled {
+	base_color: Arc<dyn Texture>,
+	metallic: Arc<dyn Texture>,
+	roughness: Arc<dyn Texture>,
+	specular: Arc<dyn Texture>,
+	clearcoat: Arc<dyn Texture>,
+	sheen: Arc<dyn Texture>,
+	transmission: Arc<dyn Texture>,
+}
+
+// GGX alpha of the clearcoat layer.
+const CLEARCOAT_ALPHA: f64 = 0.05;
+
+// Index of refraction for transmission.
+const IOR: f64 = 1.5;
+
+impl Principled {
+	/// A rough, dielectric material with given base color,
+	/// other parameters can be set with the builder methods below.
+	pub fn new<T: Texture>(base_color: T) -> Self {
+		Self {
+			base_color: Arc::new(base_color),
+			metallic: Arc::new(Color::BLACK),
+			roughness: Arc::new(Color::new(0.5, 0.5, 0.5)),
+			specular: Arc::new(Color::new(0.5, 0.5, 0.5)),
+			clearcoat: Arc::new(Color::BLACK),
+			sheen: Arc::new(Color::BLACK),
+			transmission: Arc::new(Color::BLACK),
+		}
+	}
+
+	pub fn metallic<T: Texture>(self, metallic: T) -> Self {
+		Self {
+			metallic: Arc::new(metallic),
+			..self
+		}
+	}
+
+	pub fn roughness<T: Texture>(self, roughness: T) -> Self {
+		Self {
+			roughness: Arc::new(roughness),
+			..self
+		}
+	}
+
+	pub fn specular<T: Texture>(self, specular: T) -> Self {
+		Self {
+			specular: Arc::new(specular),
+			..self
+		}
+	}
+
+	pub fn clearcoat<T: Texture>(self, clearcoat: T) -> Self {
+		Self {
+			clearcoat: Arc::new(clearcoat),
+			..self
+		}
+	}
+
+	pub fn sheen<T: Texture>(self, sheen: T) -> Self {
+		Self {
+			sheen: Arc::new(sheen),
+			..self
+		}
+	}
+
+	pub fn transmission<T: Texture>(self, transmission: T) -> Self {
+		Self {
+			transmission: Arc::new(transmission),
+			..self
+		}
+	}
+
+	// Weights of the layers for a given view angle, see `Layers`.
+	fn layers(&self, tex_coords: Pointf, fp: &Footprint, cos_o: f64) -> Layers {
+		let scalar = |t: &Arc<dyn Texture>| (t.color_at(tex_coords).r() as f64).clamp(0.0, 1.0);
+		let base = self.base_color.color_filtered(tex_coords, fp);
+		let metallic = scalar(&self.metallic);
+		let roughness = scalar(&self.roughness);
+		let transmission = scalar(&self.transmission);
+		let sheen = scalar(&self.sheen) * schlick_weight(cos_o);
+
+		// each layer takes its share, and passes on the rest.
+		let clearcoat = scalar(&self.clearcoat) * schlick(0.04, cos_o);
+		let rest = 1.0 - clearcoat;
+		let metal = (rest * metallic) as f32 * base.apply(|f0| schlick(f0 as f64, cos_o) as f32);
+		let rest = rest * (1.0 - metallic);
+		let specular = rest * schlick(0.08 * scalar(&self.specular), cos_o);
+		let rest = rest - specular;
+		let diffuse = (rest * (1.0 - transmission)) as f32 * ((1.0 - sheen) as f32 * base + sheen as f32 * Color::WHITE);
+		let transmission = (rest * transmission) as f32 * base;
+
+		Layers {
+			alpha: f64::max(1e-3, roughness * roughness),
+			weights: [
+				Color::WHITE * (clearcoat as f32),
+				metal,
+				Color::WHITE * (specular as f32),
+				diffuse,
+				transmission,
+			],
+		}
+	}
+}
+
+// Weights of the clearcoat, metal, specular, diffuse and transmission layers,
+// in that order. They sum to at most 1.
+struct Layers {
+	alpha: f64, // GGX alpha of metal and specular
+	weights: [Color; 5],
+}
+
+const CLEARCOAT: usize = 0;
+const METAL: usize = 1;
+const SPECULAR: usize = 2;
+const DIFFUSE: usize = 3;
+const TRANSMISSION: usize = 4;
+
+impl Material for Principled {
+	fn shade(&self, s: &Scene, r: &Ray, h: &HitCoords, rng: &mut Rng, depth: u32) -> Color {
+		let mut geo_norm = h.geom_normal();
+		let mut shd_norm = h.shading_normal();
+		if geo_norm.dot(r.dir) > 0.0 {
+			// Rays hitting the back of a transmissive material are inside it,
+			// with a probability equal to the transmission (as light only enters that often).
+			// Others are shaded as two-sided.
+			let transmission = (self.transmission.color_at(h.tex_coords).r() as f64).clamp(0.0, 1.0);
+			if transmission > 0.0 && (rng.random() as f64) < transmission {
+				return self.shade_exit(s, r, h, rng, depth);
+			}
+			geo_norm = -geo_norm;
+			shd_norm = -shd_norm;
+		}
+		if shd_norm.dot(r.dir) >= 0.0 {
+			shd_norm = geo_norm; // grazing incidence
+		}
+
+		let p = r.at(h.t) + TINY * geo_norm;
+		let frame = Frame::new(shd_norm, Vector::EX);
+		let wo = frame.to_local(-r.dir);
+		let layers = self.layers(h.tex_coords, &h.footprint, wo[2]);
+		let w = &layers.weights;
+		let ggx = Ggx::new((layers.alpha, layers.alpha));
+		let clearcoat = Ggx::new((CLEARCOAT_ALPHA, CLEARCOAT_ALPHA));
+
+		let mut acc = Color::BLACK;
+
+		// direct
+		for l in s.lights() {
+			let (lpos, intens) = l.sample(rng, p);
+			if intens == (Color::BLACK) {
+				continue; // potential shortcut for directional lights
+			}
+
+			let ldelta = lpos - p;
+			let ldir = ldelta.normalized();
+			let wi = frame.to_local(ldir);
+			if wi[2] <= 0.0 || ldir.dot(geo_norm) <= 0.0 {
+				continue; // backlit
+			}
+
+			// microfacet brdf D G / (4 cos_o cos_i), times cos_i, relative to Matte (times pi).
+			// The Fresnel factors are in the layer weights.
+			let m = (wo + wi).normalized();
+			let glossy = |ggx: &Ggx| (PI * ggx.d(m) * ggx.g2(wo, wi) / (4.0 * wo[2])) as f32;
+			let f = glossy(&clearcoat) * w[CLEARCOAT] + glossy(&ggx) * (w[METAL] + w[SPECULAR]) + (wi[2] as f32) * w[DIFFUSE];
+			if f.max() < 1e-6 {
+				continue; // don't bother tracing shadow ray
+			}
+
+			let sec = Ray::new(p, ldir);
+			let intens = s.occlude(&sec, ldelta.len(), intens);
+			acc += intens * f;
+		}
+
+		// indirect: pick one layer, with probability proportional to its weight.
+		let total: f32 = w.iter().map(|w| w.max()).sum();
+		if total <= 0.0 {
+			return acc;
+		}
+		let mut x = rng.random() * total;
+		let mut layer = 0;
+		for (i, w) in w.iter().enumerate() {
+			if w.max() > 0.0 {
+				layer = i;
+				if x < w.max() {
+					break;
+				}
+				x -= w.max();
+			}
+		}
+		let weight = w[layer] * (total / w[layer].max());
+
+		let (u, v) = rng.quasi_random2();
+		let uv = (u as f64, v as f64);
+		let (sec, f) = match layer {
+			DIFFUSE => (Ray::new(p, cosine_sphere(uv, shd_norm)), 1.0),
+			TRANSMISSION => {
+				let dir = refract(r.dir, shd_norm, 1.0 / IOR, wo[2]).unwrap_or_else(|| reflect(r.dir, shd_norm));
+				(Ray::new(p - 2.0 * TINY * geo_norm, dir), 1.0)
+			}
+			_ => {
+				let ggx = if layer == CLEARCOAT { &clearcoat } else { &ggx };
+				let wi = reflect(-wo, ggx.sample_visible_normal(wo, uv));
+				if wi[2] <= 0.0 {
+					return acc;
+				}
+				(Ray::new(p, frame.to_world(wi)), ggx.g2(wo, wi) / ggx.g1(wo))
+			}
+		};
+		if layer != TRANSMISSION && sec.dir.dot(geo_norm) <= 0.0 {
+			return acc;
+		}
+		// transmitted rays may see lights, which are not sampled directly through the surface.
+		let light = if layer == TRANSMISSION {
+			s.lightfield(&sec, rng, depth + 1)
+		} else {
+			s.lightfield_indirect(&sec, rng, depth + 1)
+		};
+		acc + light * weight * (f as f32)
+	}
+}
+
+impl Principled {
+	// Shade a ray that hits the surface from inside (transmission).
+	// Like Dielectric: reflect or refract, with exact Fresnel probabilities.
+	fn shade_exit(&self, s: &Scene, r: &Ray, h: &HitCoords, rng: &mut Rng, depth: u32) -> Color {
+		let geo_norm = -h.geom_normal();
+		let mut shd_norm = -h.shading_normal();
+		if shd_norm.dot(r.dir) >= 0.0 {
+			shd_norm = geo_norm;
+		}
+		let cos_i = -r.dir.dot(shd_norm);
+		let p = r.at(h.t);
+		let sec = match refract(r.dir, shd_norm, IOR, cos_i) {
+			Some(dir) if (rng.random() as f64) >= fresnel_dielectric(cos_i, IOR) => Ray::new(p - TINY * geo_norm, dir),
+			_ => Ray::new(p + TINY * geo_norm, reflect(r.dir, shd_norm)),
+		};
+		s.lightfield(&sec, rng, depth + 1)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::{default_scene, test_rng};
+
+	// Average color of the back of plane z = 0, seen and lit from below, against a black background.
+	fn back_side(m: &Principled) -> f32 {
+		let scene = Scene {
+			lights: vec![point_light(Point(0., 0., -2.), Color::WHITE)],
+			..default_scene()
+		};
+		let r = Ray::new(Point(0., 0., -1.), Vector::EZ);
+		let mut h = HitCoords::background(r.dir.into());
+		h.t = 1.0;
+		h.geom_normalf = Vectorf::EZ;
+		h.shading_normalf = Vectorf::EZ;
+		let mut trng = test_rng();
+		let n = 1000;
+		let mut acc = 0.0;
+		for i in 0..n {
+			acc += m.shade(&scene, &r, &h, &mut trng.for_pix((0, 0), i), 0).r();
+		}
+		acc / n as f32
+	}
+
+	// An open surface is only seen through from behind as much as it transmits.
+	#[test]
+	fn test_back_side() {
+		let gray = |x: f32| Color::new(x, x, x);
+		let opaque = back_side(&Principled::new(Color::WHITE));
+		assert!(opaque > 0.0);
+
+		// mostly shaded like the opaque side (the diffuse layer is also 5% weaker).
+		let low = back_side(&Principled::new(Color::WHITE).transmission(gray(0.05)));
+		assert!(low > 0.8 * opaque && low <= opaque, "{} {}", low, opaque);
+
+		// glass: the light behind the ray is not seen through it.
+		assert_eq!(back_side(&Principled::new(Color::WHITE).transmission(Color::WHITE)), 0.0);
+	}
+
+	// Layer weights must sum to at most 1 (for a white base color), at any angle.
+	#[test]
+	fn test_energy() {
+		let gray = |x: f32| Color::new(x, x, x);
+		for &metallic in &[0.0, 0.5, 1.0] {
+			for &clearcoat in &[0.0, 1.0] {
+				for &transmission in &[0.0, 0.3, 1.0] {
+					let m = Principled::new(Color::WHITE)
+						.metallic(gray(metallic))
+						.clearcoat(gray(clearcoat))
+						.transmission(gray(transmission))
+						.sheen(Color::WHITE)
+						.specular(Color::WHITE);
+					for &cos_o in &[1.0, 0.5, 0.1, 0.0] {
+						let l = m.layers(Pointf::default(), &Footprint::default(), cos_o);
+						let total = l.weights.iter().fold(Color::BLACK, |acc, &w| acc + w);
+						for c in 0..3 {
+							assert!((total[c] - 1.0).abs() < 1e-5, "{:?}", total);
+						}
+					}
+				}
+			}
+		}
+
+		// colored base: less than 1, metal is tinted.
+		let m = Principled::new(Color::RED).metallic(Color::WHITE);
+		let l = m.layers(Pointf::default(), &Footprint::default(), 1.0);
+		assert_eq!(l.weights[METAL], Color::RED);
+	}
+}
@@ -10,16 +10,3 @@ impl Material for Reflective {
 		s.lightfield(&sec, rng, depth + 1) * self.0
 	}
 }
-
-// reflect v along normal vector n.
-//      n
-//  v   |   reflected
-//   \  |  /
-//    \θ|θ/
-//     \|/
-//  --------- surface.
-//
-// See https://en.wikipedia.org/wiki/Ray_tracing_(graphics)#Example
-fn reflect(v: Vector, n: Vector) -> Vector {
-	v - 2.0 * v.dot(n) * n
-}
@@ -1,18 +1,149 @@
-//use super::internal::*;
-//use super::*;
+use super::internal::*;
+use super::*;
 
-pub struct Specular {
+/// A glossy material with (normalized) Blinn-Phong reflectance.
+/// E.g.: the highlights on plastic, varnish, polished stone.
+///
+/// The texture determines the specular reflectivity in each point,
+/// the exponent determines the sharpness of the highlights
+/// (from about 1 for very rough to 1000+ for almost mirror-like).
+///
+/// Like `Matte`, light sources are sampled directly, and one
+/// importance-sampled random ray is added for the indirect illumination.
+/// For plastic-like materials, blend with a matte base color. E.g.:
+///
+///     use brilliance::*;
+///     let plastic = Blend::new(Matte::new(RED), 0.9, Specular::new(WHITE, 100.0), 0.1);
+///
+/// See https://en.wikipedia.org/wiki/Blinn%E2%80%93Phong_reflection_model.
+pub struct Specular<T: Texture> {
+	tex: T,
 	exponent: f32,
 }
 
-impl Specular {
-	pub fn new(exponent: f32) -> Self {
-		Self { exponent }
+impl<T: Texture> Specular<T> {
+	pub fn new(tex: T, exponent: f32) -> Self {
+		Self { tex, exponent }
+	}
+
+	// BRDF value for (normalized) directions towards the light and camera,
+	// relative to Matte (i.e. times pi), so that both can be blended.
+	fn brdf(&self, n: Vector, to_light: Vector, to_cam: Vector) -> f64 {
+		let half = (to_light + to_cam).normalized();
+		let cos_h = f64::max(0.0, half.dot(n));
+		self.norm() * cos_h.powf(self.exponent as f64)
+	}
+
+	// Normalization factor so that no more light is reflected than received.
+	// See http://www.farbrausch.de/~fg/stuff/phong.pdf
+	fn norm(&self) -> f64 {
+		let exp = self.exponent as f64;
+		(exp + 2.0) * (exp + 4.0) / (8.0 * (f64::powf(2.0, -exp / 2.0) + exp))
+	}
+
+	// Importance sample a reflected direction, for random numbers (u, v).
+	// Returns the direction and its weight brdf * cos(theta) / pdf (relative to Matte),
+	// or None if the direction points below the surface.
+	//
+	// The half vector is sampled proportional to cos^exponent, and the camera direction reflected around it.
+	// The pdf of the resulting direction is (exp+1)/(2pi) cos^exp(theta_h) / (4 (to_cam . half)),
+	// so most factors of the weight cancel.
+	fn sample(&self, uv: (f64, f64), n: Vector, to_cam: Vector) -> Option<(Vector, f64)> {
+		let exp = self.exponent as f64;
+		let half = cosine_power_sphere(uv, n, exp);
+		let dir = reflect(-to_cam, half);
+		let costheta = dir.dot(n);
+		let cos_oh = to_cam.dot(half);
+		if costheta <= 0.0 || cos_oh <= 0.0 {
+			return None;
+		}
+		Some((dir, self.norm() * 8.0 / (exp + 1.0) * costheta * cos_oh))
+	}
+}
+
+impl<T: Texture> Material for Specular<T> {
+	fn shade(&self, s: &Scene, r: &Ray, h: &HitCoords, rng: &mut Rng, depth: u32) -> Color {
+		let mut acc = Color::BLACK;
+
+		// If neccessary, flip the normal vectors to point towards the camera.
+		let mut geo_norm = h.geom_normal();
+		let mut shd_norm = h.shading_normal();
+		if geo_norm.dot(r.dir) > 0.0 {
+			geo_norm = -geo_norm;
+			shd_norm = -shd_norm;
+		}
+
+		let p = r.at(h.t) + TINY * geo_norm;
+		let to_cam = -r.dir;
+
+		// direct
+		for l in s.lights() {
+			let (lpos, intens) = l.sample(rng, p);
+			if intens == (Color::BLACK) {
+				continue; // potential shortcut for directional lights
+			}
+
+			let ldelta = lpos - p;
+			let ldir = ldelta.normalized();
+			let costheta = ldir.dot(shd_norm);
+			if costheta <= 0.0 {
+				continue; // backlit
+			}
+
+			let f = self.brdf(shd_norm, ldir, to_cam);
+			if f == 0.0 {
+				continue; // outside of highlight, don't bother tracing shadow ray
+			}
+
+			let sec = Ray::new(p, ldir);
+			let intens = s.occlude(&sec, ldelta.len(), intens);
+			acc += intens * ((f * costheta) as f32);
+		}
+
+		// indirect
+		let (u, v) = rng.quasi_random2();
+		if let Some((dir, weight)) = self.sample((u as f64, v as f64), shd_norm, to_cam) {
+			if dir.dot(geo_norm) > 0.0 {
+				let sec = Ray::new(p, dir);
+				acc += s.lightfield_indirect(&sec, rng, depth + 1) * (weight as f32);
+			}
+		}
+
+		acc * self.tex.color_filtered(h.tex_coords, &h.footprint)
 	}
 }
 
-//impl Material for Specular {
-//	fn shade(&self, s: &Scene, r: &Ray, h: &HitCoords, rng: &mut Rng, depth: u32) -> Color {
-//
-//	}
-//}
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	// The fraction of light reflected (directional albedo),
+	// estimated with importance sampling, must not exceed 1.
+	// (Blinn-Phong loses energy at grazing angles, but is normalized at normal incidence).
+	#[test]
+	fn test_energy() {
+		let n = Vector::EY;
+		for &exp in &[1.0, 10.0, 100.0, 1000.0] {
+			let mat = Specular::new(Color::WHITE, exp);
+			for &angle in &[0.0, 45.0, 80.0] {
+				let to_cam = Vector(f64::sin(angle * DEG), f64::cos(angle * DEG), 0.0);
+				let m = 100;
+				let mut sum = 0.0;
+				for i in 0..m {
+					for j in 0..m {
+						let uv = ((i as f64 + 0.5) / m as f64, (j as f64 + 0.5) / m as f64);
+						if let Some((dir, weight)) = mat.sample(uv, n, to_cam) {
+							assert!((reflect(-to_cam, (dir + to_cam).normalized()) - dir).len() < 1e-9);
+							sum += weight;
+						}
+					}
+				}
+				let albedo = sum / (m * m) as f64;
+				assert!(albedo < 1.01);
+				if angle == 0.0 {
+					assert!(albedo > 0.99);
+				}
+			}
+		}
+	}
+}
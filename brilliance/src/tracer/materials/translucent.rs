@@ -17,6 +17,10 @@ impl<T: Texture, S: Texture> Material for Translucent<T, S> {
 		orig * self.transmitted.color_at(pos.into())
 	}
 
+	fn is_opaque(&self) -> bool {
+		false
+	}
+
 	fn shade(&self, s: &Scene, r: &Ray, h: &HitCoords, rng: &mut Rng, depth: u32) -> Color {
 		let mut acc = Color::BLACK;
 
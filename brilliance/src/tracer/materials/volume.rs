@@ -0,0 +1,56 @@
+use super::internal::*;
+use super::*;
+use std::sync::Arc;
+
+/// Volume fills a closed shape with a participating `Medium`,
+/// e.g. a cloud of smoke, a block of wax, or a beam of light through a dusty room.
+///
+/// The surface itself is invisible: rays pass it unchanged (no reflection or refraction),
+/// and scatter inside according to the medium.
+/// Like `Dielectric`, the inside is the side opposite to the geometric normal,
+/// so the shape should be closed and consistently oriented.
+///
+/// Objects inside the volume are shaded as if the medium were not there,
+/// and volumes should not overlap.
+/// Light scattered many times (e.g. in dense, bright media like wax) is cut off by
+/// the scene's maximum recursion depth, so such volumes may need a higher depth.
+///
+///     use brilliance::*;
+///     use std::sync::Arc;
+///     let wax = Volume::new(Arc::new(HomogeneousMedium::new(Color::new(0.1, 0.3, 0.5), Color::new(20.0, 20.0, 20.0))));
+///     let candle = Sphere::new(Point(0., 0., 0.), 1.0).paint(wax);
+pub struct Volume {
+	medium: Arc<dyn Medium>,
+}
+
+impl Volume {
+	pub fn new(medium: Arc<dyn Medium>) -> Self {
+		Self { medium }
+	}
+}
+
+impl Material for Volume {
+	// Only used when wrapped in another material,
+	// the Scene handles volume boundaries itself (see `Material::medium`).
+	fn shade(&self, s: &Scene, r: &Ray, h: &HitCoords, rng: &mut Rng, depth: u32) -> Color {
+		let entering = h.geom_normal().dot(r.dir) < 0.0;
+		let sec = Ray::new(r.at(h.t + 2.0 * TINY), r.dir);
+		if entering {
+			s.lightfield_in(&sec, Some(self.medium.as_ref()), rng, depth)
+		} else {
+			s.lightfield(&sec, rng, depth)
+		}
+	}
+
+	fn occlude(&self, c: Color, _: Point) -> Color {
+		c
+	}
+
+	fn is_opaque(&self) -> bool {
+		false
+	}
+
+	fn medium(&self) -> Option<&dyn Medium> {
+		Some(self.medium.as_ref())
+	}
+}
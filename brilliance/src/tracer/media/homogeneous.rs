@@ -0,0 +1,116 @@
+use super::*;
+
+/// A medium with the same density everywhere, like fog or the inside of wax.
+///
+/// Absorption and scattering coefficients (per unit length) are given per color channel.
+/// E.g. for skin, red light is absorbed less than green and blue,
+/// so it penetrates deeper.
+///
+///     use brilliance::*;
+///     let fog = HomogeneousMedium::new(Color::new(0.01, 0.01, 0.01), Color::new(0.05, 0.05, 0.05)).with_asymmetry(0.7);
+pub struct HomogeneousMedium {
+	sigma_a: Color,
+	sigma_s: Color,
+	phase: HenyeyGreenstein,
+}
+
+impl HomogeneousMedium {
+	/// Medium with absorption coefficient `sigma_a` and scattering coefficient `sigma_s`,
+	/// scattering isotropically.
+	pub fn new(sigma_a: Color, sigma_s: Color) -> Self {
+		Self {
+			sigma_a,
+			sigma_s,
+			phase: HenyeyGreenstein::new(0.0),
+		}
+	}
+
+	/// Scatter anisotropically, following the Henyey-Greenstein phase function with asymmetry `g`.
+	pub fn with_asymmetry(self, g: f64) -> Self {
+		Self {
+			phase: HenyeyGreenstein::new(g),
+			..self
+		}
+	}
+
+	fn sigma_t(&self) -> Color {
+		self.sigma_a + self.sigma_s
+	}
+}
+
+impl Medium for HomogeneousMedium {
+	fn sample(&self, r: &Ray, max_t: f64, rng: &mut Rng) -> MediumSample {
+		// Sample a distance using the extinction of one color channel, chosen at random.
+		// The weight divides by the probability averaged over all channels,
+		// so that each channel is still estimated correctly.
+		let sigma_t = self.sigma_t();
+		let channel = usize::min(2, (rng.random() * 3.0) as usize);
+		let st = sigma_t[channel] as f64;
+		let t = if st == 0.0 { INF } else { -f64::ln(1.0 - rng.random() as f64) / st };
+
+		if t < max_t {
+			let tr = self.transmittance(r, 0.0, t);
+			let pdf = (sigma_t * tr).average();
+			MediumSample::Scatter {
+				t,
+				weight: self.sigma_s * tr * (1.0 / pdf),
+			}
+		} else {
+			let tr = self.transmittance(r, 0.0, max_t);
+			let p = tr.average();
+			if p == 0.0 {
+				return MediumSample::Pass { weight: Color::BLACK };
+			}
+			MediumSample::Pass { weight: tr * (1.0 / p) }
+		}
+	}
+
+	fn transmittance(&self, _: &Ray, t0: f64, t1: f64) -> Color {
+		let d = (t1 - t0) as f32;
+		self.sigma_t().apply(|s| if s == 0.0 { 1.0 } else { f32::exp(-s * d) })
+	}
+
+	fn phase(&self) -> HenyeyGreenstein {
+		self.phase
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::test_rng;
+
+	// Scattering and passing weights must average to the exact transmittance.
+	#[test]
+	fn test_sample() {
+		let m = HomogeneousMedium::new(Color::new(0.1, 0.2, 0.0), Color::new(0.5, 1.0, 0.0));
+		let r = Ray::new(Point(0., 0., 0.), Vector::EX);
+		let max_t = 2.0;
+		let mut trng = test_rng();
+		let mut rng = trng.for_pix((0, 0), 0);
+
+		// with a black surface at max_t, and a scattered radiance 1,
+		// the expected result is the integral over sigma_s exp(-sigma_t t) from 0 to max_t.
+		let n = 100000;
+		let mut scattered = Color::BLACK;
+		let mut passed = Color::BLACK;
+		for _ in 0..n {
+			match m.sample(&r, max_t, &mut rng) {
+				MediumSample::Scatter { t, weight } => {
+					assert!(t < max_t);
+					scattered += weight * (1.0 / n as f32);
+				}
+				MediumSample::Pass { weight } => passed += weight * (1.0 / n as f32),
+			}
+		}
+
+		let tr = m.transmittance(&r, 0.0, max_t);
+		for c in 0..3 {
+			let (sa, ss) = ([0.1, 0.2, 0.0][c], [0.5, 1.0, 0.0][c]);
+			let st = sa + ss;
+			let want = if st == 0.0 { 0.0 } else { ss / st * (1.0 - f32::exp(-st * 2.0)) };
+			assert!((scattered[c] - want).abs() < 0.01, "{}: {} != {}", c, scattered[c], want);
+			assert!((passed[c] - tr[c]).abs() < 0.01, "{}: {} != {}", c, passed[c], tr[c]);
+		}
+	}
+}
@@ -0,0 +1,7 @@
+pub mod grid;
+pub mod homogeneous;
+
+pub use grid::*;
+pub use homogeneous::*;
+
+pub use super::*;
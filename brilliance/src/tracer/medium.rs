@@ -0,0 +1,101 @@
+use super::*;
+
+/// A Medium fills space with particles that absorb and scatter light,
+/// like fog, smoke, or the inside of wax and skin.
+///
+/// A medium fills either the whole `Scene` (see `Scene::medium`),
+/// or the inside of a closed shape painted with the `Volume` material.
+pub trait Medium: Send + Sync {
+	/// Sample the distance at which ray `r` first scatters,
+	/// if it does so before reaching a surface at distance `max_t`.
+	fn sample(&self, r: &Ray, max_t: f64, rng: &mut Rng) -> MediumSample;
+
+	/// Fraction of light transmitted along ray `r`, between distances `t0` and `t1`
+	/// (i.e. not absorbed or scattered away). Used for shadow rays.
+	fn transmittance(&self, r: &Ray, t0: f64, t1: f64) -> Color;
+
+	/// Distribution of directions into which light is scattered.
+	fn phase(&self) -> HenyeyGreenstein;
+}
+
+/// Outcome of `Medium::sample`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediumSample {
+	/// The ray scatters at distance `t`. The scattered light is to be multiplied by `weight`.
+	Scatter { t: f64, weight: Color },
+	/// The ray reaches the surface. Light coming from there is to be multiplied by `weight`.
+	Pass { weight: Color },
+}
+
+/// Henyey-Greenstein phase function: the probability density of scattering
+/// by an angle with cosine `cos` (per unit solid angle).
+///
+/// Asymmetry parameter `g` is the average cosine of the scattering angle:
+/// 0 scatters isotropically, positive values forward (e.g. fog, ~0.8), negative values backward.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HenyeyGreenstein {
+	pub g: f64,
+}
+
+impl HenyeyGreenstein {
+	pub fn new(g: f64) -> Self {
+		debug_assert!(g > -1.0 && g < 1.0);
+		Self { g }
+	}
+
+	/// Probability density of scattering from direction `dir` into `scattered`,
+	/// where `cos` = `dir.dot(scattered)`.
+	pub fn eval(&self, cos: f64) -> f64 {
+		let g = self.g;
+		let denom = 1.0 + g * g - 2.0 * g * cos;
+		(1.0 - g * g) / (4.0 * PI * denom * f64::sqrt(denom))
+	}
+
+	/// Map a point (u,v) from the unit square to a scattering direction for a ray traveling in direction `dir`,
+	/// distributed according to the phase function.
+	pub fn sample(&self, dir: Vector, (u, v): (f64, f64)) -> Vector {
+		let g = self.g;
+		let cos = if g.abs() < 1e-3 {
+			1.0 - 2.0 * u
+		} else {
+			let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * u);
+			(1.0 + g * g - s * s) / (2.0 * g)
+		};
+		let cos = cos.clamp(-1.0, 1.0);
+		let sin = f64::sqrt(1.0 - cos * cos);
+		let phi = 2.0 * PI * v;
+		make_basis(dir) * Vector(sin * f64::cos(phi), sin * f64::sin(phi), cos)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_henyey_greenstein() {
+		for &g in &[0.0, 0.5, -0.3, 0.9] {
+			let hg = HenyeyGreenstein::new(g);
+
+			// normalized over the sphere, and average cosine equals g.
+			let n = 10000;
+			let (mut norm, mut avg_cos) = (0.0, 0.0);
+			for i in 0..n {
+				let cos = -1.0 + 2.0 * (i as f64 + 0.5) / n as f64;
+				norm += hg.eval(cos) * 2.0 * PI * 2.0 / n as f64;
+				avg_cos += cos * hg.eval(cos) * 2.0 * PI * 2.0 / n as f64;
+			}
+			assert!((norm - 1.0).abs() < 1e-3, "g={}: {}", g, norm);
+			assert!((avg_cos - g).abs() < 1e-3, "g={}: {}", g, avg_cos);
+
+			// samples follow the distribution.
+			let mut sampled = 0.0;
+			for i in 0..n {
+				let d = hg.sample(Vector::EZ, ((i as f64 + 0.5) / n as f64, 0.3));
+				assert!(d.is_normalized());
+				sampled += d[2] / n as f64;
+			}
+			assert!((sampled - g).abs() < 1e-3, "g={}: {}", g, sampled);
+		}
+	}
+}
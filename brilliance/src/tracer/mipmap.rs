@@ -0,0 +1,152 @@
+use super::*;
+
+/// MipMap is an image texture with a pyramid of down-scaled copies (mip levels),
+/// used to average over the pixel footprint (see `Texture::color_filtered`).
+/// Distant textured surfaces then show the average color of the texels they cover,
+/// rather than aliasing and shimmering between frames.
+///
+/// Filtering is trilinear: bilinear within the two levels closest to the footprint size,
+/// and linear between those. The footprint is taken as wide as its major axis,
+/// so surfaces seen at grazing angles get blurry. `with_anisotropy` avoids this by
+/// averaging several lookups along the major axis, from a finer level.
+///
+///     use brilliance::*;
+///     let img = Image::<Color>::from_fn((64, 64), |x, y| if (x + y) % 2 == 0 { BLACK } else { WHITE });
+///     let tex = MipMap::new(img).with_anisotropy(8);
+pub struct MipMap<C> {
+	base: Image<C>,            // original image (level 0), e.g. sRGB bytes.
+	levels: Vec<Image<Color>>, // levels 1, 2, ..., halving in size down to 1x1.
+	max_aniso: u32,
+}
+
+impl<C> MipMap<C>
+where
+	C: Into<Color> + Copy + Default + Send + Sync + 'static,
+{
+	pub fn new(base: Image<C>) -> Self {
+		let mut levels = vec![half(&base)];
+		while levels.last().unwrap().dimensions() != (1, 1) {
+			let next = half(levels.last().unwrap());
+			levels.push(next);
+		}
+		Self { base, levels, max_aniso: 1 }
+	}
+
+	/// Filter anisotropically, with up to `max` lookups along the footprint's major axis
+	/// (typically 4-16). Sharper at grazing angles, but slower.
+	pub fn with_anisotropy(self, max: u32) -> Self {
+		Self {
+			max_aniso: u32::max(1, max),
+			..self
+		}
+	}
+
+	/// Number of levels, including the original image.
+	pub fn num_levels(&self) -> usize {
+		self.levels.len() + 1
+	}
+
+	// Bilinear lookup in level `l`.
+	fn bilinear(&self, l: usize, p: Pointf) -> Color {
+		match l {
+			0 => bilinear(&self.base, (p[0], p[1])),
+			_ => bilinear(&self.levels[l - 1], (p[0], p[1])),
+		}
+	}
+
+	// Bilinear lookup in (fractional) level `lod`, linearly interpolated between levels.
+	fn trilinear(&self, p: Pointf, lod: f32) -> Color {
+		let lod = lod.clamp(0.0, self.levels.len() as f32);
+		let l0 = lod.floor() as usize;
+		let w = lod - l0 as f32;
+		let c0 = self.bilinear(l0, p);
+		if w == 0.0 {
+			return c0;
+		}
+		c0 * (1.0 - w) + self.bilinear(l0 + 1, p) * w
+	}
+}
+
+// Image of half the size (rounded down, at least 1x1),
+// box filtered over 2x2 texels (or 2x1, 1x2 for sides of size 1).
+fn half<C: Into<Color> + Copy + Default>(img: &Image<C>) -> Image<Color> {
+	let (w, h) = img.dimensions();
+	let at = |x, y| -> Color { img.at((x, y)).into() };
+	Image::from_fn((u32::max(1, w / 2), u32::max(1, h / 2)), |x, y| {
+		let (x0, y0) = (2 * x, 2 * y);
+		let (x1, y1) = (u32::min(x0 + 1, w - 1), u32::min(y0 + 1, h - 1));
+		(at(x0, y0) + at(x1, y0) + at(x0, y1) + at(x1, y1)) * 0.25
+	})
+}
+
+impl<C> Texture for MipMap<C>
+where
+	C: Into<Color> + Copy + Default + Send + Sync + 'static,
+{
+	fn color_at(&self, p: Pointf) -> Color {
+		self.bilinear(0, p)
+	}
+
+	fn color_filtered(&self, p: Pointf, fp: &Footprint) -> Color {
+		// footprint axes, in texels of the original image.
+		let (w, h) = self.base.dimensions();
+		let texels = |d: Vectorf| f32::hypot(d[0] * w as f32, d[1] * h as f32);
+		let (lx, ly) = (texels(fp.dx), texels(fp.dy));
+		let (major, minor, axis) = if lx > ly { (lx, ly, fp.dx) } else { (ly, lx, fp.dy) };
+		if major == 0.0 || !major.is_finite() {
+			return self.color_at(p); // no footprint
+		}
+
+		// lookups spaced along the major axis, each as wide as the minor axis
+		// (or wider, if limited by max_aniso).
+		let n = f32::min((major / minor).ceil(), self.max_aniso as f32) as u32;
+		let lod = f32::log2(major / n as f32);
+		let mut acc = Color::BLACK;
+		for i in 0..n {
+			let s = (i as f32 + 0.5) / n as f32 - 0.5;
+			acc += self.trilinear(p + axis * s, lod);
+		}
+		acc * (1.0 / n as f32)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_levels() {
+		let tex = MipMap::new(Image::from_fn((8, 3), |x, _| Color::new(x as f32, 0., 0.)));
+		assert_eq!(tex.num_levels(), 4);
+		assert_eq!(tex.levels[0].dimensions(), (4, 1));
+		assert_eq!(tex.levels[2].dimensions(), (1, 1));
+		assert_eq!(tex.levels[2].at((0, 0)), Color::new(3.5, 0., 0.));
+	}
+
+	#[test]
+	fn test_filtered() {
+		// vertical stripes, one texel wide.
+		let n = 64;
+		let stripes = || Image::from_fn((n, n), |x, _| if x % 2 == 0 { Color::BLACK } else { Color::WHITE });
+		let texel = 1.0 / n as f32;
+		let p = Pointf(5.0 / (n - 1) as f32, 0.5, 0.0); // center of a white column
+		let fp = |dx: Vectorf, dy: Vectorf| Footprint { dx, dy };
+
+		let tex = MipMap::new(stripes());
+		// no footprint, or smaller than a texel: unfiltered.
+		assert_eq!(tex.color_filtered(p, &Footprint::default()), Color::WHITE);
+		let small = fp(Vectorf(0.5 * texel, 0., 0.), Vectorf(0., 0.5 * texel, 0.));
+		assert_eq!(tex.color_filtered(p, &small), Color::WHITE);
+		// large footprint: average gray.
+		let large = fp(Vectorf(8.0 * texel, 0., 0.), Vectorf(0., 8.0 * texel, 0.));
+		assert!((tex.color_filtered(p, &large).r() - 0.5).abs() < 1e-3);
+
+		// elongated along the stripes.
+		let elongated = fp(Vectorf(0., 16.0 * texel, 0.), Vectorf(0.5 * texel, 0., 0.));
+		// isotropic: blurred across the stripes
+		assert!((tex.color_filtered(p, &elongated).r() - 0.5).abs() < 1e-3);
+		// anisotropic: sharp
+		let tex = MipMap::new(stripes()).with_anisotropy(16);
+		assert!((tex.color_filtered(p, &elongated).r() - 1.0).abs() < 1e-3);
+	}
+}
@@ -1,3 +1,4 @@
+pub mod accumulator;
 pub mod boundingbox;
 pub mod camera;
 pub mod color;
@@ -9,6 +10,9 @@ pub mod light;
 pub mod lights;
 pub mod material;
 pub mod materials;
+pub mod media;
+pub mod medium;
+pub mod mipmap;
 pub mod object;
 pub mod objects;
 pub mod postprocess;
@@ -18,9 +22,11 @@ pub mod rng;
 pub mod scene;
 pub mod tex_coords;
 pub mod texture;
+pub mod units;
 pub mod uvmap;
 pub mod view;
 
+pub use accumulator::*;
 pub use boundingbox::*;
 pub use camera::*;
 pub use color::*;
@@ -32,6 +38,9 @@ pub use light::*;
 pub use lights::*;
 pub use material::*;
 pub use materials::*;
+pub use media::*;
+pub use medium::*;
+pub use mipmap::*;
 pub use object::*;
 pub use objects::*;
 pub use postprocess::*;
@@ -42,6 +51,7 @@ pub use scene::*;
 pub use tex_coords::*;
 pub use tex_coords::*;
 pub use texture::*;
+pub use units::*;
 pub use uvmap::*;
 pub use view::*;
 
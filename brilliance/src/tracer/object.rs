@@ -1,9 +1,81 @@
 use super::*;
+use std::sync::Arc;
 
 pub trait Object: Bounded + Send + Sync {
 	fn intersect<'s>(&'s self, r: &Ray, h: &mut HitRecord<'s>);
+
+	/// Whether the ray hits the object at a distance below `max_t`,
+	/// and if so, whether it is blocked completely (see `Occlusion`).
+	/// Unlike `intersect`, this may return as soon as an opaque hit is found,
+	/// rather than the closest one, which makes it cheaper for shadow rays.
+	fn occluded(&self, r: &Ray, max_t: f64) -> Occlusion {
+		let background = Flat::new(Color::BLACK);
+		let mut h = HitRecord::background(&background, r.dir.into());
+		h.coords.t = max_t;
+		self.intersect(r, &mut h);
+		if h.t() < max_t {
+			Occlusion::of(h.material)
+		} else {
+			Occlusion::Clear
+		}
+	}
+}
+
+/// Result of an any-hit query (`Object::occluded`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Occlusion {
+	/// Nothing in the way.
+	Clear,
+	/// Only hits that may let some light through (translucent, dissolving or volume surfaces).
+	/// The hits still need to be visited in order, via `intersect`.
+	Partial,
+	/// At least one opaque hit: no light gets through.
+	Opaque,
+}
+
+impl Occlusion {
+	/// Occlusion by a hit with material `m`.
+	pub fn of<M: Material + ?Sized>(m: &M) -> Self {
+		if m.is_opaque() {
+			Occlusion::Opaque
+		} else {
+			Occlusion::Partial
+		}
+	}
+
+	/// Occlusion by any of several objects.
+	/// Stops at the first opaque one, so pass a lazy iterator.
+	pub fn any<I: IntoIterator<Item = Occlusion>>(iter: I) -> Self {
+		let mut acc = Occlusion::Clear;
+		for o in iter {
+			if o == Occlusion::Opaque {
+				return o;
+			}
+			acc = Occlusion::max(acc, o);
+		}
+		acc
+	}
 }
 
 pub trait Bounded {
 	fn bounds(&self) -> BoundingBox;
 }
+
+impl<O: Object> Object for Arc<O> {
+	fn intersect<'s>(&'s self, r: &Ray, h: &mut HitRecord<'s>) {
+		let inner: &O = self.borrow();
+		inner.intersect(r, h)
+	}
+
+	fn occluded(&self, r: &Ray, max_t: f64) -> Occlusion {
+		let inner: &O = self.borrow();
+		inner.occluded(r, max_t)
+	}
+}
+
+impl<O: Bounded> Bounded for Arc<O> {
+	fn bounds(&self) -> BoundingBox {
+		let inner: &O = self.borrow();
+		inner.bounds()
+	}
+}
@@ -33,6 +33,35 @@ impl BoundingBox4 {
 
 	#[inline]
 	pub fn intersects_fast(&self, start: Pointf, invdir: Vectorf, max: f32) -> [bool; 4] {
+		let (ten, reten, tex) = self.slabs(start, invdir);
+		[
+			tex[0] >= reten[0] && ten[0] < max,
+			tex[1] >= reten[1] && ten[1] < max,
+			tex[2] >= reten[2] && ten[2] < max,
+			tex[3] >= reten[3] && ten[3] < max,
+		]
+	}
+
+	/// Distance along the ray where it enters each of the 4 boxes
+	/// (0 if it starts inside), or `INF32` if the box is not intersected before `max`.
+	/// Used to visit boxes in front-to-back order.
+	#[inline]
+	pub fn entry_distances(&self, r: &Ray, max: f32) -> [f32; 4] {
+		let start = Vectorf::from(r.start);
+		let invdir = Vectorf::from(r.dir).inv();
+		let (ten, reten, tex) = self.slabs(start, invdir);
+		let mut t = [INF32; 4];
+		for i in 0..4 {
+			if tex[i] >= reten[i] && ten[i] < max {
+				t[i] = reten[i];
+			}
+		}
+		t
+	}
+
+	// Ray entry distance, entry distance clamped to 0, and exit distance for each box.
+	#[inline]
+	fn slabs(&self, start: Pointf, invdir: Vectorf) -> ([f32; 4], [f32; 4], [f32; 4]) {
 		let start0 = F32x4::from(start[0]);
 		let start1 = F32x4::from(start[1]);
 		let start2 = F32x4::from(start[2]);
@@ -63,12 +92,7 @@ impl BoundingBox4 {
 		let reten = F32x4::max(F32x4::from(0.), ten).array();
 		let ten = ten.array();
 
-		[
-			tex[0] >= reten[0] && ten[0] < max,
-			tex[1] >= reten[1] && ten[1] < max,
-			tex[2] >= reten[2] && ten[2] < max,
-			tex[3] >= reten[3] && ten[3] < max,
-		]
+		(ten, reten, tex)
 	}
 }
 
@@ -107,6 +107,11 @@ impl Shape for Face {
 		let tex_coords = Pointf::new(u, v, 0.0);
 
 		h.update_checked(t64, n.into(), shad_norm, tex_coords);
+
+		// tangents: texture coordinates change along the edges a, b.
+		let t = |i: usize| (self.attr[i].tex_coords.u_f32() as f64, self.attr[i].tex_coords.v_f32() as f64);
+		let (t0, t1, t2) = (t(0), t(1), t(2));
+		h.set_tangents(a, b, (t1.0 - t0.0, t1.1 - t0.1), (t2.0 - t0.0, t2.1 - t0.1));
 		true
 	}
 
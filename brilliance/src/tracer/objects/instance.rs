@@ -0,0 +1,144 @@
+use super::*;
+use std::sync::Arc;
+
+/// A placed copy of a shared Object (typically a mesh).
+///
+/// Many instances can share the same underlying object,
+/// each with its own transform and optionally its own material:
+///
+///     use brilliance::*;
+///     use std::sync::Arc;
+///     let rock = Arc::new(Sphere::new(Point(0., 0., 0.), 1.0).paint(matte(WHITE)));
+///     let objects: Vec<DynObj> = (0..100)
+///         .map(|i| DynObj::new(Instance::new(rock.clone(), Transform::translate(Vector(i as f64, 0., 0.)))))
+///         .collect();
+///
+pub struct Instance<O: Object> {
+	obj: TransformedObj<Arc<O>>,
+	material: Option<Arc<dyn Material>>,
+}
+
+impl<O: Object> Instance<O> {
+	/// Place a shared `obj` in world space by transform `t`.
+	pub fn new(obj: Arc<O>, t: Transform) -> Self {
+		Self {
+			obj: TransformedObj::new(obj, t),
+			material: None,
+		}
+	}
+
+	/// Render this instance with material `mat`,
+	/// rather than the material(s) of the shared object.
+	pub fn with_material(mut self, mat: Arc<dyn Material>) -> Self {
+		self.material = Some(mat);
+		self
+	}
+}
+
+impl<O: Object> Object for Instance<O> {
+	fn intersect<'s>(&'s self, r: &Ray, h: &mut HitRecord<'s>) {
+		match &self.material {
+			None => self.obj.intersect(r, h),
+			Some(mat) => {
+				let t = h.t();
+				self.obj.intersect(r, h);
+				if h.t() < t {
+					h.material = mat;
+				}
+			}
+		}
+	}
+
+	fn occluded(&self, r: &Ray, max_t: f64) -> Occlusion {
+		match (&self.material, self.obj.occluded(r, max_t)) {
+			(Some(mat), o) if o != Occlusion::Clear => Occlusion::of(mat.as_ref()),
+			(_, o) => o,
+		}
+	}
+}
+
+impl<O: Object> Bounded for Instance<O> {
+	fn bounds(&self) -> BoundingBox {
+		self.obj.bounds()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::{default_scene, test_rng};
+
+	// Sphere of radius 1 around the origin.
+	fn ball(c: Color) -> Arc<WithMaterial<Sphere, Flat<Color>>> {
+		Arc::new(Sphere::new(Point(0., 0., 0.), 2.0).paint(Flat::new(c)))
+	}
+
+	// Intersect, and return the hit distance, normal and color.
+	fn hit(obj: &dyn Object, r: &Ray) -> (f64, Vector, Color) {
+		let background = Flat::new(Color::BLACK);
+		let mut h = HitRecord::background(&background, r.dir.into());
+		obj.intersect(r, &mut h);
+		h.coords.geom_normalf.normalize();
+		let mut trng = test_rng();
+		let c = h.material.shade(&default_scene(), r, &h.coords, &mut trng.for_pix((0, 0), 0), 0);
+		(h.t(), h.coords.geom_normal(), c)
+	}
+
+	#[test]
+	fn test_transform() {
+		// radius 2, around (0, 0, -5).
+		let t = Transform::scale(Point(0., 0., 0.), 2.0).before(&Transform::translate(Vector(0., 0., -5.)));
+		let inst = Instance::new(ball(Color::WHITE), t);
+
+		let (t, n, _) = hit(&inst, &Ray::new(Point(0., 0., 0.), -Vector::EZ));
+		assert!((t - 3.0).abs() < 1e-9);
+		assert!((n - Vector::EZ).len() < 1e-6);
+
+		// hit at y = 1, 30 degrees above the equator.
+		let (t, n, _) = hit(&inst, &Ray::new(Point(0., 1., 0.), -Vector::EZ));
+		assert!((t - (5.0 - f64::sqrt(3.0))).abs() < 1e-9);
+		assert!((n - Vector(0., 0.5, 0.5 * f64::sqrt(3.0))).len() < 1e-6);
+
+		// miss
+		let r = Ray::new(Point(0., 2.5, 0.), -Vector::EZ);
+		assert_eq!(hit(&inst, &r).0, INF);
+		assert_eq!(inst.occluded(&r, INF), Occlusion::Clear);
+	}
+
+	#[test]
+	fn test_material() {
+		let r = Ray::new(Point(0., 0., 0.), -Vector::EZ);
+		let t = Transform::translate(Vector(0., 0., -5.));
+
+		// None keeps the object's own material.
+		let inst = Instance::new(ball(Color::RED), t.clone());
+		assert_eq!(hit(&inst, &r).2, Color::RED);
+		assert_eq!(inst.occluded(&r, INF), Occlusion::Opaque);
+
+		// override
+		let inst = inst.with_material(Arc::new(Flat::new(Color::BLUE)));
+		assert_eq!(hit(&inst, &r).2, Color::BLUE);
+
+		// the override also decides what shadows look like.
+		let glass = Translucent::new(Color::WHITE, Color::BLACK);
+		let inst = Instance::new(ball(Color::RED), t).with_material(Arc::new(glass));
+		assert_eq!(inst.occluded(&r, INF), Occlusion::Partial);
+	}
+
+	#[test]
+	fn test_shared() {
+		let mesh = Arc::new(QTree::new((0..10).map(|i| Sphere::new(Point(i as f64, 0., 0.), 1.0)).collect()).paint(Matte::new(Color::WHITE)));
+		let a = Instance::new(mesh.clone(), Transform::translate(Vector(0., 0., -5.)));
+		let b = Instance::new(mesh.clone(), Transform::translate(Vector(0., 10., -5.)));
+
+		// one tree, referenced by both instances.
+		assert_eq!(Arc::strong_count(&mesh), 3);
+
+		// each sees it in its own place.
+		let down = |y: f64| Ray::new(Point(3., y, 0.), -Vector::EZ);
+		assert!((hit(&a, &down(0.0)).0 - 4.5).abs() < 1e-9);
+		assert_eq!(hit(&a, &down(10.0)).0, INF);
+		assert!((hit(&b, &down(10.0)).0 - 4.5).abs() < 1e-9);
+		assert_eq!(hit(&b, &down(0.0)).0, INF);
+	}
+}
@@ -1,21 +1,31 @@
 pub mod boundingbox4;
 pub mod face;
+pub mod instance;
+pub mod polygon;
 pub mod qtree;
+pub mod quad;
 pub mod shape;
 pub mod sphere;
 pub mod transformed;
 pub mod vertex;
+pub mod wavefrontmtl;
 pub mod wavefrontobj;
+pub mod with_alpha;
 pub mod with_material;
 
 pub use boundingbox4::*;
 pub use face::*;
+pub use instance::*;
+pub use polygon::*;
 pub use qtree::*;
+pub use quad::*;
 pub use shape::*;
 pub use sphere::*;
 pub use transformed::*;
 pub use vertex::*;
+pub use wavefrontmtl::*;
 pub use wavefrontobj::*;
+pub use with_alpha::*;
 pub use with_material::*;
 
 pub use super::*;
@@ -0,0 +1,139 @@
+use super::*;
+
+/// A mesh element: either a triangle or a quadrilateral.
+pub enum Polygon {
+	Triangle(Face),
+	Quad(Quad),
+}
+
+impl Bounded for Polygon {
+	fn bounds(&self) -> BoundingBox {
+		match self {
+			Polygon::Triangle(f) => f.bounds(),
+			Polygon::Quad(q) => q.bounds(),
+		}
+	}
+}
+
+impl Shape for Polygon {
+	#[inline]
+	fn intersect_coords(&self, r: &Ray, h: &mut HitCoords) -> bool {
+		match self {
+			Polygon::Triangle(f) => f.intersect_coords(r, h),
+			Polygon::Quad(q) => q.intersect_coords(r, h),
+		}
+	}
+}
+
+/// Normal vector of a (possibly non-planar) polygon,
+/// with length twice the polygon's area, after Newell's method.
+/// Points along the right-handed normal for vertices in counterclockwise order.
+pub fn polygon_normal(v: &[Pointf]) -> Vectorf {
+	let mut n = Vectorf::default();
+	for i in 0..v.len() {
+		let (a, b) = (v[i], v[(i + 1) % v.len()]);
+		n[0] += (a[1] - b[1]) * (a[2] + b[2]);
+		n[1] += (a[2] - b[2]) * (a[0] + b[0]);
+		n[2] += (a[0] - b[0]) * (a[1] + b[1]);
+	}
+	n
+}
+
+/// Split a simple (possibly concave) polygon into triangles by ear clipping.
+/// Returns triangles as indices into `v`, with the same winding as the polygon.
+///
+/// The polygon is projected onto the plane perpendicular to its normal.
+/// Degenerate or self-intersecting polygons are split into a triangle fan
+/// once no more ears can be found.
+pub fn triangulate(v: &[Pointf]) -> Vec<[usize; 3]> {
+	debug_assert!(v.len() >= 3);
+
+	// project onto the plane where the polygon has the largest area,
+	// so that counterclockwise (seen from the normal) becomes positive area in 2D.
+	let n = polygon_normal(v);
+	let axis = Vectorf(n[0].abs(), n[1].abs(), n[2].abs()).argmax();
+	let (x, y) = if n[axis] >= 0.0 {
+		((axis + 1) % 3, (axis + 2) % 3)
+	} else {
+		((axis + 2) % 3, (axis + 1) % 3)
+	};
+	let p: Vec<(f32, f32)> = v.iter().map(|v| (v[x], v[y])).collect();
+
+	let mut tris = Vec::with_capacity(v.len() - 2);
+	let mut rem: Vec<usize> = (0..v.len()).collect();
+	while rem.len() > 3 {
+		let m = rem.len();
+		let ear = (0..m).find(|&i| {
+			let (a, b, c) = (rem[(i + m - 1) % m], rem[i], rem[(i + 1) % m]);
+			// convex corner, and no other vertex inside.
+			cross2(p[a], p[b], p[c]) > 0.0 && rem.iter().all(|&j| j == a || j == b || j == c || !in_triangle(p[j], p[a], p[b], p[c]))
+		});
+		match ear {
+			Some(i) => {
+				tris.push([rem[(i + m - 1) % m], rem[i], rem[(i + 1) % m]]);
+				rem.remove(i);
+			}
+			None => break,
+		}
+	}
+
+	// last triangle, or fan for the degenerate remainder.
+	for i in 1..(rem.len() - 1) {
+		tris.push([rem[0], rem[i], rem[i + 1]]);
+	}
+	tris
+}
+
+// z-component of (b-a) x (c-b): positive for a counterclockwise turn.
+fn cross2(a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> f32 {
+	(b.0 - a.0) * (c.1 - b.1) - (b.1 - a.1) * (c.0 - b.0)
+}
+
+// is p inside or on the edge of counterclockwise triangle (a, b, c)?
+fn in_triangle(p: (f32, f32), a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> bool {
+	cross2(a, b, p) >= 0.0 && cross2(b, c, p) >= 0.0 && cross2(c, a, p) >= 0.0
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn area(v: &[Pointf], tris: &[[usize; 3]]) -> f32 {
+		tris.iter().map(|t| polygon_normal(&[v[t[0]], v[t[1]], v[t[2]]]).len() / 2.0).sum()
+	}
+
+	#[test]
+	fn test_triangulate() {
+		// convex pentagon, in the XZ plane, clockwise seen from +Y.
+		let v = [
+			Pointf(0., 0., 0.),
+			Pointf(0., 0., 1.),
+			Pointf(1., 0., 2.),
+			Pointf(2., 0., 1.),
+			Pointf(2., 0., 0.),
+		];
+		let tris = triangulate(&v);
+		assert_eq!(tris.len(), 3);
+		assert!((area(&v, &tris) - 3.0).abs() < 1e-6);
+		for t in &tris {
+			// same winding as the polygon
+			assert!(polygon_normal(&[v[t[0]], v[t[1]], v[t[2]]]).dot(polygon_normal(&v)) > 0.0);
+		}
+
+		// concave "L" shape in the XY plane, counterclockwise.
+		let v = [
+			Pointf(0., 0., 0.),
+			Pointf(2., 0., 0.),
+			Pointf(2., 1., 0.),
+			Pointf(1., 1., 0.),
+			Pointf(1., 2., 0.),
+			Pointf(0., 2., 0.),
+		];
+		let tris = triangulate(&v);
+		assert_eq!(tris.len(), 4);
+		assert!((area(&v, &tris) - 3.0).abs() < 1e-6);
+		for t in &tris {
+			assert!(polygon_normal(&[v[t[0]], v[t[1]], v[t[2]]])[2] > 0.0);
+		}
+	}
+}
@@ -13,17 +13,88 @@ enum Children<T> {
 
 use Children::*;
 
+/// Options controlling how a `QTree` is built.
+#[derive(Clone, Debug)]
+pub struct BuildOptions {
+	/// How children are partitioned into 4 subtrees.
+	pub split: Split,
+
+	/// Number of bins per axis considered by `Split::Sah`.
+	pub sah_bins: usize,
+}
+
+/// Strategy for partitioning a `QTree`'s children.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Split {
+	/// Split into (roughly) power-of-4 sized groups by count,
+	/// along the longest axis. Fast to build.
+	Median,
+
+	/// Binned Surface Area Heuristic: pick split planes that minimize
+	/// the expected intersection cost. Slower to build, faster to intersect.
+	Sah,
+}
+
+impl Default for BuildOptions {
+	fn default() -> Self {
+		Self {
+			split: Split::Median,
+			sah_bins: 16,
+		}
+	}
+}
+
+impl BuildOptions {
+	/// Default options, but split using the Surface Area Heuristic.
+	pub fn sah() -> Self {
+		Self {
+			split: Split::Sah,
+			..Self::default()
+		}
+	}
+}
+
 impl<T: Bounded> QTree<T> {
+	/// Build a tree over the given children, using default `BuildOptions`.
+	/// `ch` may be empty, in which case the tree is never intersected.
 	pub fn new(ch: Vec<T>) -> Self {
-		assert!(ch.len() > 0);
+		Self::with_options(ch, &BuildOptions::default())
+	}
 
-		if ch.len() <= 4 {
+	/// Build a tree over the given children, using the given `BuildOptions`.
+	pub fn with_options(ch: Vec<T>, opts: &BuildOptions) -> Self {
+		if ch.is_empty() {
+			Self::empty()
+		} else if ch.len() <= 4 {
 			Self::build_leafs(ch)
 		} else {
-			Self::build_inner(ch)
+			match opts.split {
+				Split::Median => Self::build_median(ch, opts),
+				Split::Sah => Self::build_sah(ch, opts),
+			}
 		}
 	}
 
+	fn empty() -> Self {
+		let empty = BoundingBox::empty(Pointf::ZERO);
+		Self {
+			bb4: BoundingBox4::new([&empty, &empty, &empty, &empty]),
+			ch: Leafs(Vec::new()),
+		}
+	}
+
+	/// Number of leaf objects in the tree.
+	pub fn len(&self) -> usize {
+		match &self.ch {
+			Leafs(ch) => ch.len(),
+			Inner(ch4) => ch4.iter().map(|ch| ch.len()).sum(),
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
 	fn build_leafs(ch: Vec<T>) -> Self {
 		assert!(ch.len() > 0 && ch.len() <= 4);
 		let mut bbs = Vec::<BoundingBox>::with_capacity(4);
@@ -42,16 +113,42 @@ impl<T: Bounded> QTree<T> {
 		}
 	}
 
-	fn build_inner(ch: Vec<T>) -> Self {
+	fn build_median(ch: Vec<T>, opts: &BuildOptions) -> Self {
 		let cut = Self::split4(ch.len());
 		let (l, r) = Self::split_at(ch, cut[0] + cut[1]);
 		let (a, b) = Self::split_at(l, cut[0]);
 		let (c, d) = Self::split_at(r, cut[2]);
 
-		let a = Self::new(a);
-		let b = Self::new(b);
-		let c = Self::new(c);
-		let d = Self::new(d);
+		Self::build_inner([a, b, c, d], opts)
+	}
+
+	// Collapse a binary SAH tree into a 4-wide node:
+	// repeatedly split the group with the largest surface area until there are 4 groups.
+	fn build_sah(ch: Vec<T>, opts: &BuildOptions) -> Self {
+		debug_assert!(ch.len() > 4);
+		let mut groups = vec![ch];
+		while groups.len() < 4 {
+			let largest = (0..groups.len())
+				.filter(|&i| groups[i].len() > 1)
+				.max_by(|&i, &j| cmp(bounds_of(&groups[i]).area(), bounds_of(&groups[j]).area()))
+				.unwrap(); // there are more children than groups, so some group can be split
+			let (l, r) = Self::split_sah(groups.swap_remove(largest), opts.sah_bins);
+			groups.push(l);
+			groups.push(r);
+		}
+
+		let d = groups.pop().unwrap();
+		let c = groups.pop().unwrap();
+		let b = groups.pop().unwrap();
+		let a = groups.pop().unwrap();
+		Self::build_inner([a, b, c, d], opts)
+	}
+
+	fn build_inner([a, b, c, d]: [Vec<T>; 4], opts: &BuildOptions) -> Self {
+		let a = Self::with_options(a, opts);
+		let b = Self::with_options(b, opts);
+		let c = Self::with_options(c, opts);
+		let d = Self::with_options(d, opts);
 
 		Self {
 			bb4: BoundingBox4::new([&a.bounds(), &b.bounds(), &c.bounds(), &d.bounds()]),
@@ -59,6 +156,74 @@ impl<T: Bounded> QTree<T> {
 		}
 	}
 
+	// Split in two groups, at the plane that minimizes the Surface Area Heuristic
+	//   area(left) * len(left) + area(right) * len(right).
+	// Candidate planes are the boundaries between `bins` equally sized bins
+	// spanning the children's centers, along each axis.
+	fn split_sah(ch: Vec<T>, bins: usize) -> (Vec<T>, Vec<T>) {
+		debug_assert!(ch.len() > 1);
+		let bins = usize::max(bins, 2);
+		let cb = bounds_of_centers(&ch);
+		let bin_of = |axis: usize, ch: &T| {
+			let (min, max) = (cb.min[axis], cb.max[axis]);
+			let b = ((ch.bounds().center()[axis] - min) / (max - min) * (bins as f32)) as usize;
+			usize::min(b, bins - 1)
+		};
+
+		// best (cost, axis, bin): split right after bin.
+		let mut best: Option<(f32, usize, usize)> = None;
+		for axis in 0..3 {
+			if cb.max[axis] <= cb.min[axis] {
+				continue; // all centers in the same plane
+			}
+
+			let mut bin_bounds: Vec<Option<BoundingBox>> = vec![None; bins];
+			let mut bin_count = vec![0; bins];
+			for ch in &ch {
+				let b = bin_of(axis, ch);
+				bin_bounds[b] = Some(join_opt(&bin_bounds[b], &ch.bounds()));
+				bin_count[b] += 1;
+			}
+
+			// area of bins i.. (i.e., to the right of split i-1)
+			let mut right_area = vec![0.0; bins];
+			let mut acc: Option<BoundingBox> = None;
+			for i in (1..bins).rev() {
+				if let Some(bb) = &bin_bounds[i] {
+					acc = Some(join_opt(&acc, bb));
+				}
+				right_area[i] = acc.as_ref().map(BoundingBox::area).unwrap_or(0.0);
+			}
+
+			let mut acc: Option<BoundingBox> = None;
+			let mut left_count = 0;
+			for i in 0..(bins - 1) {
+				if let Some(bb) = &bin_bounds[i] {
+					acc = Some(join_opt(&acc, bb));
+				}
+				left_count += bin_count[i];
+				let right_count = ch.len() - left_count;
+				if left_count == 0 || right_count == 0 {
+					continue;
+				}
+				let left_area = acc.as_ref().map(BoundingBox::area).unwrap_or(0.0);
+				let cost = left_area * (left_count as f32) + right_area[i + 1] * (right_count as f32);
+				if best.map(|(c, _, _)| cost < c).unwrap_or(true) {
+					best = Some((cost, axis, i));
+				}
+			}
+		}
+
+		match best {
+			Some((_, axis, split)) => ch.into_iter().partition(|ch| bin_of(axis, ch) <= split),
+			// all centers coincide: no plane separates them, split by count.
+			None => {
+				let n = ch.len();
+				Self::split_at(ch, n / 2)
+			}
+		}
+	}
+
 	fn split4(n: usize) -> [usize; 4] {
 		let a = nearest_pow4(n / 4);
 		let b = nearest_pow4((n - a) / 3);
@@ -74,13 +239,6 @@ impl<T: Bounded> QTree<T> {
 		let size = bb.max - bb.min;
 		let splitdir = size.argmax();
 
-		fn cmp(a: f32, b: f32) -> Ordering {
-			if a < b {
-				Ordering::Less
-			} else {
-				Ordering::Greater
-			}
-		}
 		ch.sort_unstable_by(|a, b| cmp(a.bounds().center()[splitdir], b.bounds().center()[splitdir]));
 
 		let right = ch.split_off(i);
@@ -89,6 +247,29 @@ impl<T: Bounded> QTree<T> {
 	}
 }
 
+fn cmp(a: f32, b: f32) -> Ordering {
+	if a < b {
+		Ordering::Less
+	} else {
+		Ordering::Greater
+	}
+}
+
+fn join_opt(a: &Option<BoundingBox>, b: &BoundingBox) -> BoundingBox {
+	match a {
+		None => b.clone(),
+		Some(a) => a.join(b),
+	}
+}
+
+fn bounds_of<T: Bounded>(ch: &[T]) -> BoundingBox {
+	let mut bb = ch[0].bounds();
+	for ch in &ch[1..] {
+		bb = bb.join(&ch.bounds())
+	}
+	bb
+}
+
 //
 fn bounds_of_centers<T: Bounded>(ch: &[T]) -> BoundingBox {
 	let pt = ch[0].bounds().center();
@@ -107,82 +288,82 @@ impl<T: Bounded> Bounded for QTree<T> {
 
 impl<T: Shape> Shape for QTree<T> {
 	fn intersect_coords(&self, r: &Ray, h: &mut HitCoords) -> bool {
-		let inter = self.bb4.intersects_slow(r, h.t as f32);
+		let t = self.bb4.entry_distances(r, h.t as f32);
 		let mut hit = false;
-		// TODO: could drill down into the nearest child first,
-		// potentially return early because of occlusion.
+		// visit children front-to-back, skip those behind the closest hit so far.
 		match &self.ch {
-			Leafs(ch4) => {
-				// TODO: skip bounds checks
-				if inter[0] {
-					hit |= ch4[0].intersect_coords(r, h);
-				}
-				if inter[1] {
-					hit |= ch4[1].intersect_coords(r, h);
-				}
-				if inter[2] {
-					hit |= ch4[2].intersect_coords(r, h);
-				}
-				if inter[3] {
-					hit |= ch4[3].intersect_coords(r, h);
+			Leafs(ch) => {
+				for &i in &front_to_back(&t) {
+					// leafs may hold fewer than 4 children,
+					// the remaining boxes are padding.
+					if i < ch.len() && t[i] < h.t as f32 {
+						hit |= ch[i].intersect_coords(r, h);
+					}
 				}
 			}
 			Inner(ch4) => {
-				if inter[0] {
-					hit |= ch4[0].intersect_coords(r, h);
-				}
-				if inter[1] {
-					hit |= ch4[1].intersect_coords(r, h);
-				}
-				if inter[2] {
-					hit |= ch4[2].intersect_coords(r, h);
-				}
-				if inter[3] {
-					hit |= ch4[3].intersect_coords(r, h);
+				for &i in &front_to_back(&t) {
+					if t[i] < h.t as f32 {
+						hit |= ch4[i].intersect_coords(r, h);
+					}
 				}
 			}
 		}
 		hit
 	}
+
+	fn intersects(&self, r: &Ray, max_t: f64) -> bool {
+		let inter = self.bb4.intersects_slow(r, max_t as f32);
+		match &self.ch {
+			Leafs(ch) => ch.iter().enumerate().any(|(i, ch)| inter[i] && ch.intersects(r, max_t)),
+			Inner(ch4) => ch4.iter().enumerate().any(|(i, ch)| inter[i] && ch.intersects(r, max_t)),
+		}
+	}
 }
 
 impl<T: Object> Object for QTree<T> {
 	fn intersect<'s>(&'s self, r: &Ray, h: &mut HitRecord<'s>) {
-		let inter = self.bb4.intersects_slow(r, h.t() as f32);
-		// TODO: could drill down into the nearest child first,
-		// potentially return early because of occlusion.
+		let t = self.bb4.entry_distances(r, h.t() as f32);
+		// visit children front-to-back, skip those behind the closest hit so far.
 		match &self.ch {
-			Leafs(ch4) => {
-				// TODO: skip bounds checks
-				if inter[0] {
-					ch4[0].intersect(r, h);
-				}
-				if inter[1] {
-					ch4[1].intersect(r, h);
-				}
-				if inter[2] {
-					ch4[2].intersect(r, h);
-				}
-				if inter[3] {
-					ch4[3].intersect(r, h);
+			Leafs(ch) => {
+				for &i in &front_to_back(&t) {
+					if i < ch.len() && t[i] < h.t() as f32 {
+						ch[i].intersect(r, h);
+					}
 				}
 			}
 			Inner(ch4) => {
-				if inter[0] {
-					ch4[0].intersect(r, h);
-				}
-				if inter[1] {
-					ch4[1].intersect(r, h);
-				}
-				if inter[2] {
-					ch4[2].intersect(r, h);
-				}
-				if inter[3] {
-					ch4[3].intersect(r, h);
+				for &i in &front_to_back(&t) {
+					if t[i] < h.t() as f32 {
+						ch4[i].intersect(r, h);
+					}
 				}
 			}
 		}
 	}
+
+	fn occluded(&self, r: &Ray, max_t: f64) -> Occlusion {
+		let inter = self.bb4.intersects_slow(r, max_t as f32);
+		// stop at the first opaque hit, in any order.
+		match &self.ch {
+			Leafs(ch) => Occlusion::any(ch.iter().enumerate().filter(|(i, _)| inter[*i]).map(|(_, ch)| ch.occluded(r, max_t))),
+			Inner(ch4) => Occlusion::any(ch4.iter().enumerate().filter(|(i, _)| inter[*i]).map(|(_, ch)| ch.occluded(r, max_t))),
+		}
+	}
+}
+
+// Indices 0..4, sorted by increasing distance `t`.
+#[inline]
+fn front_to_back(t: &[f32; 4]) -> [usize; 4] {
+	let mut o = [0, 1, 2, 3];
+	// sorting network for 4 elements.
+	for &(a, b) in &[(0, 1), (2, 3), (0, 2), (1, 3), (1, 2)] {
+		if t[o[b]] < t[o[a]] {
+			o.swap(a, b);
+		}
+	}
+	o
 }
 
 fn nearest_pow4(n: usize) -> usize {
@@ -205,6 +386,71 @@ fn nearest_pow(n: u32, base: u32) -> u32 {
 mod test {
 	use super::*;
 
+	// Median and SAH trees must find the same intersections.
+	#[test]
+	fn test_sah() {
+		let spheres = || {
+			let mut s = Vec::new();
+			for i in 0..300 {
+				let x = ((i * 7) % 13) as f64;
+				let y = ((i * 5) % 11) as f64;
+				let z = (i % 17) as f64 * (i % 3) as f64;
+				s.push(Sphere::new(Point(x, y, z), 0.2 + 0.1 * (i % 5) as f64));
+			}
+			s
+		};
+		let median = QTree::with_options(spheres(), &BuildOptions::default());
+		let sah = QTree::with_options(spheres(), &BuildOptions::sah());
+		assert_eq!(median.len(), 300);
+		assert_eq!(sah.len(), 300);
+		assert_eq!(median.bounds(), sah.bounds());
+
+		let start = Point(-5.0, -3.0, -7.0);
+		for i in 0..400 {
+			let target = Point((i % 20) as f64 * 0.7, (i / 20) as f64 * 0.6, 4.0);
+			let r = Ray::new(start, (target - start).normalized());
+			let mut a = HitCoords::background(r.dir.into());
+			let mut b = HitCoords::background(r.dir.into());
+			assert_eq!(median.intersect_coords(&r, &mut a), sah.intersect_coords(&r, &mut b));
+			assert_eq!(a.t, b.t);
+		}
+	}
+
+	// Any-hit queries must agree with closest-hit queries.
+	#[test]
+	fn test_occluded() {
+		let mut spheres = Vec::new();
+		for i in 0..100 {
+			spheres.push(Sphere::new(Point((i % 10) as f64, (i / 10) as f64, 0.0), 0.5));
+		}
+		let tree = QTree::new(spheres);
+		let start = Point(4.5, 4.5, 5.0);
+		for i in 0..200 {
+			let target = Point((i % 20) as f64 * 0.6 - 1.0, (i / 20) as f64 * 1.1 - 1.0, 0.0);
+			let r = Ray::new(start, (target - start).normalized());
+			for &max_t in &[1.0, 4.8, 5.0, 7.0, INF] {
+				let mut h = HitCoords::background(r.dir.into());
+				h.t = max_t;
+				assert_eq!(tree.intersects(&r, max_t), tree.intersect_coords(&r, &mut h));
+			}
+		}
+	}
+
+	#[test]
+	fn test_front_to_back() {
+		assert_eq!(front_to_back(&[1.0, 2.0, 3.0, 4.0]), [0, 1, 2, 3]);
+		assert_eq!(front_to_back(&[4.0, 3.0, 2.0, 1.0]), [3, 2, 1, 0]);
+		assert_eq!(front_to_back(&[2.0, INF32, 0.0, 1.0]), [2, 3, 0, 1]);
+	}
+
+	#[test]
+	fn test_empty() {
+		let tree = QTree::<Sphere>::new(vec![]);
+		assert!(tree.is_empty());
+		let r = Ray::new(Point(0., 0., 1.), -Vector::EZ);
+		assert!(!tree.intersect_coords(&r, &mut HitCoords::background(r.dir.into())));
+	}
+
 	#[test]
 	fn test_nearest_pow2() {
 		assert_eq!(nearest_pow(1, 2), 1);
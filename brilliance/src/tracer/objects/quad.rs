@@ -1,117 +1,201 @@
 use super::*;
 
+/// A quadrilateral with vertices `v0, v1, v2, v3` (in order around the edge).
+///
+/// Non-planar quads are rendered as the bilinear patch
+///   p(u, v) = (1-u)(1-v) v0 + u(1-v) v1 + u v v2 + (1-u) v v3,
+/// which reduces to the flat quadrilateral if the vertices are co-planar.
+/// Shading normals and texture coordinates are interpolated bilinearly.
 pub struct Quad {
-	pos: [Pointf; 4]
+	pos: [Pointf; 4],
 	attr: [Attr; 4],
 }
 
 impl Quad {
 	pub fn new(v: [Vertex; 4]) -> Self {
+		let [v0, v1, v2, v3] = v;
 		Self {
-			pos: [v[0].pos, v[1].pos, v[2].pos, v[3].pos],
-			attr: [v[0].attr, v[1].attr, v[2].attr, v[3].attr],
+			pos: [v0.pos, v1.pos, v2.pos, v3.pos],
+			attr: [v0.attr, v1.attr, v2.attr, v3.attr],
 		}
 	}
+
+	/// Vertex positions.
+	pub fn vertices(&self) -> &[Pointf; 4] {
+		&self.pos
+	}
+
+	/// Vertex attributes.
+	pub fn attrs(&self) -> &[Attr; 4] {
+		&self.attr
+	}
+
+	// Bilinear interpolation of per-vertex values.
+	#[inline]
+	fn lerp<T>(&self, (u, v): (f32, f32), x: [T; 4]) -> T
+	where
+		T: std::ops::Mul<f32, Output = T> + std::ops::Add<T, Output = T>,
+	{
+		let [x0, x1, x2, x3] = x;
+		x0 * ((1. - u) * (1. - v)) + x1 * (u * (1. - v)) + x2 * (u * v) + x3 * ((1. - u) * v)
+	}
 }
 
-impl Bounded for Face {
+impl Bounded for Quad {
 	fn bounds(&self) -> BoundingBox {
-		BoundingBox::from(&self.pos)
+		// a bilinear patch lies inside the convex hull of its vertices.
+		BoundingBox::from_points(self.pos.iter())
 	}
 }
 
 impl Shape for Quad {
 	#[inline]
 	fn intersect_coords(&self, r: &Ray, h: &mut HitCoords) -> bool {
+		// Ray / bilinear patch intersection after
+		// A. Reshetov, "Cool Patches: A Geometric Approach to Ray/Bilinear Patch Intersections",
+		// Ray Tracing Gems (2019).
+		// Done in f64 precision, like Face, to avoid bleeding.
+		let p: [Point; 4] = [self.pos[0].into(), self.pos[1].into(), self.pos[2].into(), self.pos[3].into()];
+		let (q00, q10, q11, q01) = (p[0], p[1], p[2], p[3]);
+		let d = r.dir;
+
+		let e10 = q10 - q00;
+		let e11 = q11 - q10;
+		let e00 = q01 - q00;
+		let qn = e10.cross(q01 - q11);
+		let q00 = q00 - r.start;
+		let q10 = q10 - r.start;
+
+		// u is a root of a + b u + c u^2
+		let a = q00.cross(d).dot(e00);
+		let c = qn.dot(d);
+		let b = q10.cross(d).dot(e11) - (a + c);
+		let det = b * b - 4. * a * c;
+		if det < 0.0 {
+			return false;
+		}
+		let det = det.sqrt();
+		let (u1, u2) = if c == 0.0 {
+			(-a / b, -1.0) // planar trapezoid: linear equation
+		} else {
+			let u1 = (-b - det.copysign(b)) / 2.0;
+			(u1 / c, a / u1)
+		};
+
+		// for each root u, solve for t and v.
+		let mut hit: Option<(f64, f64, f64)> = None;
+		for &u in &[u1, u2] {
+			if !(0.0..=1.0).contains(&u) {
+				continue; // handles NaN gracefully
+			}
+			let pa = q00 + u * (q10 - q00);
+			let pb = e00 + u * (e11 - e00);
+			let n = d.cross(pb);
+			let det = n.dot(n);
+			let n = n.cross(pa);
+			let t = n.dot(pb) / det;
+			let v = n.dot(d);
+			if v >= 0.0 && v <= det && t > 0.0 && t < hit.map(|h| h.0).unwrap_or(h.t) {
+				hit = Some((t, u, v / det));
+			}
+		}
 
-		todo!();
-		/*
-		let a = self.a;
-		let b = self.b;
-		let o = self.o;
-
-		let start = r.start;
-		let dir = r.dir;
-
-		let a: Vector = a.into();
-		let b: Vector = b.into();
-
-		let n = a.cross(b);
-		let o: Point = o.into();
-
-		let s = start - o;
-		let t64 = -n.dot(s) / n.dot(dir);
-		let n2 = n.dot(n);
+		let (t, u, v) = match hit {
+			None => return false,
+			Some(hit) => hit,
+		};
+
+		// geometric normal: cross product of partial derivatives.
+		let dpdu = (1. - v) * e10 + v * (q11 - q01);
+		let dpdv = (1. - u) * e00 + u * e11;
+		let geom_norm: Vectorf = dpdu.cross(dpdv).into();
+		if geom_norm.len() <= 1e-5 {
+			return false; // degenerate patch corner
+		}
 
-		// handles NaN gracefully
-		if !(t64 > 0.0 && t64 < h.t) {
+		let uv = (u as f32, v as f32);
+		let a = &self.attr;
+		let shad_norm = self.lerp(uv, [a[0].shd_normal, a[1].shd_normal, a[2].shd_normal, a[3].shd_normal]);
+		let tex_coords = self.lerp(
+			uv,
+			[
+				a[0].tex_coords.into(),
+				a[1].tex_coords.into(),
+				a[2].tex_coords.into(),
+				a[3].tex_coords.into(),
+			],
+		);
+
+		if !h.update_checked(t, geom_norm, shad_norm, tex_coords) {
 			return false;
 		}
 
-		let p = s + t64 * dir;
-		let invn2 = 1.0 / n2;
+		// tangents: chain rule through the bilinear parameters (u, v).
+		let tc = |i: usize| (a[i].tex_coords.u_f32() as f64, a[i].tex_coords.v_f32() as f64);
+		let (t0, t1, t2, t3) = (tc(0), tc(1), tc(2), tc(3));
+		let dtdu = ((1. - v) * (t1.0 - t0.0) + v * (t2.0 - t3.0), (1. - v) * (t1.1 - t0.1) + v * (t2.1 - t3.1));
+		let dtdv = ((1. - u) * (t3.0 - t0.0) + u * (t2.0 - t1.0), (1. - u) * (t3.1 - t0.1) + u * (t2.1 - t1.1));
+		h.set_tangents(dpdu, dpdv, dtdu, dtdv);
+		true
+	}
+}
 
-		// Barycentric coordinates for 3D triangle, after
-		// Peter Shirley, Fundamentals of Computer Graphics, 2nd Edition.
-		let nc = a.cross(p);
-		let nb = p.cross(b);
-		let l3 = n.dot(nc) * invn2;
-		let l2 = n.dot(nb) * invn2;
-		let l1 = 1. - l2 - l3;
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn quad(pos: [Pointf; 4]) -> Quad {
+		let n = Vectorf::EZ;
+		let v = |i: usize, u, v| Vertex {
+			pos: pos[i],
+			attr: Attr::new(n, TexCoords::new(u, v)),
+		};
+		Quad::new([v(0, 0., 0.), v(1, 1., 0.), v(2, 1., 1.), v(3, 0., 1.)])
+	}
 
-		if f64::min(f64::min(l1, l2), l3) < 0.0 {
-			return false;
+	fn intersect(q: &Quad, start: Point, dir: Vector) -> Option<HitCoords> {
+		let r = Ray::new(start, dir.normalized());
+		let mut h = HitCoords::background(r.dir.into());
+		if q.intersect_coords(&r, &mut h) {
+			Some(h)
+		} else {
+			None
 		}
+	}
 
-		let l1 = l1 as f32;
-		let l2 = l2 as f32;
-		let l3 = l3 as f32;
-
-		let shad_norm = self.attr[0].shd_normal * l1
-			+ self.attr[1].shd_normal * l2
-			+ self.attr[2].shd_normal * l3;
+	#[test]
+	fn test_planar() {
+		// unit square at z = -1, and a trapezoid.
+		for q in &[
+			quad([Pointf(0., 0., -1.), Pointf(1., 0., -1.), Pointf(1., 1., -1.), Pointf(0., 1., -1.)]),
+			quad([Pointf(0., 0., -1.), Pointf(1., 0., -1.), Pointf(0.8, 1., -1.), Pointf(0.2, 1., -1.)]),
+		] {
+			let h = intersect(q, Point(0.5, 0.25, 0.), -Vector::EZ).unwrap();
+			assert!((h.t - 1.0).abs() < 1e-9);
+			assert!((h.geom_normal() - Vector::EZ).len() < 1e-6);
+			assert!((h.tex_coords[0] - 0.5).abs() < 1e-3);
+			assert!((h.tex_coords[1] - 0.25).abs() < 1e-3);
+			assert!((h.dpdu.normalized() - Vectorf::EX).len() < 1e-3);
+
+			assert!(intersect(q, Point(0.5, 0.25, 0.), Vector::EZ).is_none());
+			assert!(intersect(q, Point(1.5, 0.25, 0.), -Vector::EZ).is_none());
+			assert!(intersect(q, Point(0.5, -0.1, 0.), -Vector::EZ).is_none());
+			assert!(intersect(q, Point(0.5, 1.1, 0.), -Vector::EZ).is_none());
+		}
+	}
 
-		h.update_unchecked(t64, n.into(), shad_norm);
-		true
-		*/
+	#[test]
+	fn test_bilinear() {
+		// saddle: z = x*y on the unit square.
+		let q = quad([Pointf(0., 0., 0.), Pointf(1., 0., 0.), Pointf(1., 1., 1.), Pointf(0., 1., 0.)]);
+		for &(x, y) in &[(0.5, 0.5), (0.1, 0.9), (0.75, 0.25)] {
+			let h = intersect(&q, Point(x, y, 2.), -Vector::EZ).unwrap();
+			assert!((h.t - (2.0 - x * y)).abs() < 1e-9);
+			// gradient of z - xy
+			assert!((h.geom_normal() - Vector(-y, -x, 1.).normalized()).len() < 1e-6);
+		}
+		// hits from below as well
+		let h = intersect(&q, Point(0.5, 0.5, -1.), Vector::EZ).unwrap();
+		assert!((h.t - 1.25).abs() < 1e-9);
 	}
 }
-
-// #[cfg(test)]
-// mod test {
-// 	use super::*;
-//
-// 	const EZ: Vector = Vector::EZ;
-//
-// 	/*
-//
-// 			 * (3,4)
-// 			/|
-// 		   / |
-// 		  /  |
-// 	(1,2)*---* (3,2)
-//
-// 	*/
-// 	#[test]
-// 	fn intersects() {
-// 		let t = Triangle::new(Point(1., 2., -1.), Point(3., 2., -1.), Point(3., 4., -1.));
-//
-// 		assert!(!t.intersects(&Ray(Point(0., 0., 0.,), -EZ)));
-// 		assert!(!t.intersects(&Ray(Point(0., 0., 0.,), EZ)));
-//
-// 		assert!(t.intersects(&Ray(Point(2., 3., 0.,), -EZ)));
-// 		assert!(!t.intersects(&Ray(Point(2., 3., 0.,), EZ)));
-//
-// 		assert!(!t.intersects(&Ray(Point(4., 3., 0.,), -EZ)));
-// 		assert!(!t.intersects(&Ray(Point(4., 3., 0.,), EZ)));
-// 		assert!(!t.intersects(&Ray(Point(2., -3., 0.,), -EZ)));
-// 		assert!(!t.intersects(&Ray(Point(2., -3., 0.,), EZ)));
-//
-// 		assert!(!t.intersects(&Ray(Point(0., 0., -2.,), EZ)));
-// 		assert!(!t.intersects(&Ray(Point(0., 0., -2.,), -EZ)));
-//
-// 		assert!(t.intersects(&Ray(Point(2., 3., -2.,), EZ)));
-// 		assert!(!t.intersects(&Ray(Point(2., 3., -2.,), -EZ)));
-// 	}
-// }
-//
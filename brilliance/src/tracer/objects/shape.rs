@@ -3,6 +3,14 @@ use super::*;
 pub trait Shape: Bounded + Send + Sync + Sized {
 	fn intersect_coords(&self, r: &Ray, h: &mut HitCoords) -> bool;
 
+	/// Whether the ray hits the shape at a distance below `max_t`.
+	/// See `Object::occluded`.
+	fn intersects(&self, r: &Ray, max_t: f64) -> bool {
+		let mut h = HitCoords::background(r.dir.into());
+		h.t = max_t;
+		self.intersect_coords(r, &mut h)
+	}
+
 	fn paint<M: Material>(self, mat: M) -> WithMaterial<Self, M> {
 		WithMaterial::new(self, mat)
 	}
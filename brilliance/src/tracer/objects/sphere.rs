@@ -48,8 +48,21 @@ impl Shape for Sphere {
 		}
 		if t > 0.0 {
 			let p = r.at(t);
-			let n = (p - self.origin).normalized().into();
-			return h.update_checked(t, n, n, self.tex_coords(p));
+			let n = (p - self.origin).normalized();
+			if !h.update_checked(t, n.into(), n.into(), self.tex_coords(p)) {
+				return false;
+			}
+
+			// tangents: derivatives of sphere_map's longitude (u) and latitude (v).
+			// They vanish at the poles.
+			let (x, y, z) = (n[0], n[1], n[2]);
+			let rho = f64::sqrt(x * x + z * z);
+			if rho > 1e-9 {
+				let r = self.r2.sqrt();
+				h.dpdu = (2.0 * PI * r * Vector(-z, 0.0, x)).into();
+				h.dpdv = (-PI * r * Vector(-y * x / rho, rho, -y * z / rho)).into();
+			}
+			return true;
 		}
 		false
 	}
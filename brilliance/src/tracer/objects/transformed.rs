@@ -1,31 +1,145 @@
 use super::*;
 
+/// An Object placed in world space by an affine `Transform`
+/// (any combination of translation, rotation, (non-uniform) scaling and shear).
+///
+/// Rays are transformed to the object's own coordinate system,
+/// intersected there, and the hit is transformed back to world space.
 pub struct TransformedObj<O: Object> {
 	orig: O,
-	scale: f64,
-	transl: Point,
+	inv: Transform,       // world to object space
+	normal: Matrix<f64>,  // object to world space, for normal vectors: inverse transpose
+	tangent: Matrix<f64>, // object to world space, for tangent vectors
+	bounds: BoundingBox,  // in world space
 }
 
 impl<O: Object> TransformedObj<O> {
-	pub fn new(orig: O, scale: f64, transl: Point) -> Self {
+	/// Place `orig` in world space by transform `t`
+	/// (which maps object coordinates to world coordinates).
+	/// `t` must be invertible.
+	pub fn new(orig: O, t: Transform) -> Self {
+		let inv = t.inverse();
+		let normal = inv.a.transpose();
+		let bounds = transform_bounds(&t, &orig.bounds());
 		Self {
 			orig,
-			scale: 1.0 / scale,
-			transl: -transl,
+			inv,
+			normal,
+			tangent: t.a,
+			bounds,
 		}
 	}
+
+	// The ray in object space, and the factor by which distances along it
+	// are larger than along the world space ray.
+	#[inline]
+	fn object_ray(&self, r: &Ray) -> (Ray, f64) {
+		let dir = self.inv.transform_vector(r.dir);
+		let scale = dir.len();
+		(Ray::new(self.inv.transform_point(r.start), dir / scale), scale)
+	}
+
+	#[inline]
+	fn world_normal(&self, n: Vectorf) -> Vectorf {
+		(self.normal * Vector::from(n)).into()
+	}
 }
 
 impl<O: Object> Object for TransformedObj<O> {
 	#[inline]
 	fn intersect<'s>(&'s self, r: &Ray, h: &mut HitRecord<'s>) {
-		let r2 = Ray::new((r.start + self.transl) * self.scale, r.dir);
-		self.orig.intersect(&r2, h)
+		let (r2, scale) = self.object_ray(r);
+
+		let world_t = h.t();
+		h.coords.t = world_t * scale;
+		self.orig.intersect(&r2, h);
+
+		if h.t() < world_t * scale {
+			// hit: back to world space
+			h.coords.t /= scale;
+			h.coords.geom_normalf = self.world_normal(h.coords.geom_normalf);
+			h.coords.shading_normalf = self.world_normal(h.coords.shading_normalf);
+			h.coords.dpdu = (self.tangent * Vector::from(h.coords.dpdu)).into();
+			h.coords.dpdv = (self.tangent * Vector::from(h.coords.dpdv)).into();
+		} else {
+			// no hit: restore exactly, avoid round-off
+			h.coords.t = world_t;
+		}
+	}
+
+	fn occluded(&self, r: &Ray, max_t: f64) -> Occlusion {
+		let (r2, scale) = self.object_ray(r);
+		self.orig.occluded(&r2, max_t * scale)
 	}
 }
 
 impl<O: Object> Bounded for TransformedObj<O> {
 	fn bounds(&self) -> BoundingBox {
-		todo!();
+		self.bounds.clone()
+	}
+}
+
+// Bounding box of a transformed bounding box (i.e. of its 8 transformed corners).
+fn transform_bounds(t: &Transform, bb: &BoundingBox) -> BoundingBox {
+	let (min, max) = (bb.min, bb.max);
+	let mut corners = Vec::with_capacity(8);
+	for &x in &[min[0], max[0]] {
+		for &y in &[min[1], max[1]] {
+			for &z in &[min[2], max[2]] {
+				let p = t.transform_point(Point(x as f64, y as f64, z as f64));
+				corners.push(Pointf::from(p));
+			}
+		}
+	}
+	BoundingBox::from_points(corners.iter())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn ellipsoid(t: Transform) -> TransformedObj<WithMaterial<Sphere, Flat<Color>>> {
+		// unit sphere, stretched along y.
+		let s = Sphere::new(Point(0., 0., 0.), 2.0).paint(Flat::new(Color::WHITE));
+		TransformedObj::new(s, Transform::scale_xyz(Point(0., 0., 0.), Vector(1., 2., 1.)).before(&t))
+	}
+
+	fn intersect(obj: &dyn Object, r: &Ray) -> HitCoords {
+		let mat = Flat::new(Color::BLACK);
+		let mut h = HitRecord::background(&mat, r.dir.into());
+		obj.intersect(r, &mut h);
+		h.coords
+	}
+
+	#[test]
+	fn test_intersect() {
+		let obj = ellipsoid(Transform::translate(Vector(0., 0., -5.)));
+
+		let r = Ray::new(Point(0., 0., 0.), -Vector::EZ);
+		let h = intersect(&obj, &r);
+		assert!((h.t - 4.0).abs() < 1e-9);
+		assert!((h.geom_normal() - Vector::EZ).len() < 1e-6);
+
+		// hit the ellipsoid x² + y²/4 + z² = 1 at y = 1.
+		let r = Ray::new(Point(0., 1., 0.), -Vector::EZ);
+		let h = intersect(&obj, &r);
+		let z = f64::sqrt(0.75);
+		assert!((h.t - (5.0 - z)).abs() < 1e-9);
+		// normal is the gradient (2x, y/2, 2z)
+		assert!((h.geom_normal() - Vector(0., 0.5, 2. * z).normalized()).len() < 1e-6);
+		assert_eq!(obj.occluded(&r, 5.0), Occlusion::Opaque);
+		assert_eq!(obj.occluded(&r, 4.0), Occlusion::Clear);
+
+		// miss: t unchanged
+		let r = Ray::new(Point(0., 3., 0.), -Vector::EZ);
+		assert_eq!(intersect(&obj, &r).t, INF);
+	}
+
+	#[test]
+	fn test_bounds() {
+		let obj = ellipsoid(Transform::rotate(Point(0., 0., 0.), Vector::EZ, 90.0 * DEG));
+		let bb = obj.bounds();
+		assert!((bb.min - Pointf(-2., -1., -1.)).len() < 1e-5);
+		assert!((bb.max - Pointf(2., 1., 1.)).len() < 1e-5);
 	}
 }
@@ -0,0 +1,362 @@
+use super::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Materials by name, as defined by `newmtl` statements.
+pub type MtlLib = HashMap<String, Mtl>;
+
+/// A material definition from a Wavefront MTL file. See
+/// http://paulbourke.net/dataformats/mtl/
+///
+/// Texture file names are kept as written in the file,
+/// they are resolved relative to the MTL file's directory by `material`.
+#[derive(Clone, Debug)]
+pub struct Mtl {
+	pub kd: Color, // diffuse reflectivity
+	pub ks: Color, // specular reflectivity
+	pub ns: f32,   // specular exponent, 0..1000
+	pub d: f32,    // dissolve (opacity). Also parsed from Tr (transparency): d = 1 - Tr
+	pub ni: f32,   // index of refraction
+	pub ke: Color, // emission
+
+	pub map_kd: Option<String>, // diffuse texture, replaces kd
+	pub map_d: Option<String>,  // opacity mask, cuts out the shape
+	pub bump: Option<String>,   // bump map (height texture)
+	pub bump_mult: f32,         // bump map strength (-bm option): height of white, in object units
+}
+
+impl Default for Mtl {
+	fn default() -> Self {
+		Self {
+			kd: Color::new(0.7, 0.7, 0.7),
+			ks: Color::BLACK,
+			ns: 0.0,
+			d: 1.0,
+			ni: 1.0,
+			ke: Color::BLACK,
+			map_kd: None,
+			map_d: None,
+			bump: None,
+			bump_mult: 1.0,
+		}
+	}
+}
+
+/// Parse a Wavefront MTL file.
+pub fn parse_mtl_file(fname: &Path) -> Result<MtlLib> {
+	parse_mtl(File::open(fname)?)
+}
+
+/// Like parse_mtl_file, but accepts a reader.
+pub fn parse_mtl<R: Read>(r: R) -> Result<MtlLib> {
+	let mut lib = MtlLib::new();
+	let mut curr: Option<(String, Mtl)> = None;
+
+	for (i, line) in BufReader::new(r).lines().enumerate() {
+		let line = line?;
+		let mut fields = line.split_ascii_whitespace();
+		let first = fields.next().unwrap_or_default();
+		let args: Vec<&str> = fields.collect();
+
+		if first == "newmtl" {
+			if let Some((name, mtl)) = curr.take() {
+				lib.insert(name, mtl);
+			}
+			if args.len() != 1 {
+				return error(format!("line {}: newmtl: need 1 argument, got: {:?}", i + 1, args));
+			}
+			curr = Some((args[0].to_owned(), Mtl::default()));
+			continue;
+		}
+
+		if first.is_empty() || first.starts_with('#') {
+			continue;
+		}
+
+		let mtl = match &mut curr {
+			Some((_, mtl)) => mtl,
+			None => return error(format!("line {}: {} before newmtl", i + 1, first)),
+		};
+		// on parse error, prefix with current line number
+		if let Err(e) = parse_mtl_line(mtl, first, &args) {
+			return error(format!("line {}: {}", i + 1, e));
+		}
+	}
+
+	if let Some((name, mtl)) = curr.take() {
+		lib.insert(name, mtl);
+	}
+	Ok(lib)
+}
+
+fn parse_mtl_line(mtl: &mut Mtl, first: &str, args: &[&str]) -> Result<()> {
+	match first {
+		"Kd" => mtl.kd = parse_color(first, args)?,
+		"Ks" => mtl.ks = parse_color(first, args)?,
+		"Ke" => mtl.ke = parse_color(first, args)?,
+		"Ns" => mtl.ns = parse_f32(first, args)?,
+		"Ni" => mtl.ni = parse_f32(first, args)?,
+		"d" => mtl.d = parse_f32(first, &args[args.len().saturating_sub(1)..])?, // ignore "-halo" option
+		"Tr" => mtl.d = 1.0 - parse_f32(first, args)?,
+		"map_Kd" => mtl.map_kd = Some(parse_map(first, args)?),
+		"map_d" => mtl.map_d = Some(parse_map(first, args)?),
+		"bump" | "map_bump" | "map_Bump" => {
+			mtl.bump = Some(parse_map(first, args)?);
+			if let Some(i) = args.iter().position(|&a| a == "-bm") {
+				mtl.bump_mult = parse_f32("-bm", &args[(i + 1)..(i + 2).min(args.len())])?;
+			}
+		}
+		_ => (), // ignore unknown statements (illum, Ka, map_Ks, ...)
+	}
+	Ok(())
+}
+
+// "Kd r g b", or "Kd r" for gray.
+fn parse_color(name: &str, args: &[&str]) -> Result<Color> {
+	match args.len() {
+		1 => {
+			let v = args[0].parse()?;
+			Ok(Color::new(v, v, v))
+		}
+		3 => Ok(Color::new(args[0].parse()?, args[1].parse()?, args[2].parse()?)),
+		_ => error(format!("{}: need 3 color components, have: {:?}", name, args)),
+	}
+}
+
+fn parse_f32(name: &str, args: &[&str]) -> Result<f32> {
+	if args.len() != 1 {
+		return error(format!("{}: need 1 number, have: {:?}", name, args));
+	}
+	Ok(args[0].parse()?)
+}
+
+// Texture map statements may have options like "-bm 0.5" or "-s 1 1 1"
+// before the file name. The file name comes last.
+fn parse_map(name: &str, args: &[&str]) -> Result<String> {
+	match args.last() {
+		Some(fname) => Ok((*fname).to_owned()),
+		None => error(format!("{}: need a file name", name)),
+	}
+}
+
+impl Mtl {
+	/// Convert to a brilliance material, with best effort:
+	///
+	///  * Kd, map_Kd: `Matte` diffuse color or texture.
+	///  * Ks, Ns: blended with `Specular` highlights.
+	///  * d, Tr: `Dissolve`, or with Ni above 1, mixed with a refracting `Dielectric` (e.g. glass).
+	///  * Ke: added as `Flat` emission.
+	///  * bump, -bm: `BumpMap`, displaced by -bm times the height (0..1) of the bump texture.
+	///
+	/// map_d is not part of the material, but cuts out the shape, see `opacity`.
+	/// Textures are loaded relative to `dir`, and shared via `textures`
+	/// between materials that use the same file.
+	/// Textures that cannot be loaded are reported and replaced by the plain color.
+	pub fn material(&self, dir: &Path, textures: &mut HashMap<String, Arc<dyn Texture>>) -> Arc<dyn Material> {
+		let diffuse: Arc<dyn Texture> = match &self.map_kd {
+			None => Arc::new(self.kd),
+			Some(fname) => load_texture(dir, fname, textures, |f| imageio::load(f).map(MipMap::new), self.kd),
+		};
+
+		// diffuse weight reduced by the specular reflectivity, to conserve energy.
+		let mut mat: Arc<dyn Material> = if self.ks != Color::BLACK {
+			let spec = self.ks.max().min(1.0);
+			Arc::new(Blend::new(Matte::new(diffuse), 1.0 - spec, Specular::new(self.ks, self.ns), 1.0))
+		} else {
+			Arc::new(Matte::new(diffuse))
+		};
+
+		if self.d < 1.0 {
+			mat = if self.ni > 1.0 {
+				// transparent parts refract, rather than letting rays pass straight through.
+				let d = self.d.max(0.0);
+				Arc::new(Mix::new(Dielectric::new(self.ni as f64), mat, Color::new(d, d, d)))
+			} else {
+				Arc::new(Dissolve::new(mat, self.d, Color::WHITE))
+			};
+		}
+
+		if let Some(fname) = &self.bump {
+			let height = load_texture(dir, fname, textures, imageio::load_linear, Color::BLACK);
+			mat = Arc::new(BumpMap::new(mat, height, self.bump_mult as f64));
+		}
+
+		if self.ke != Color::BLACK {
+			mat = Arc::new(Blend::new(mat, 1.0, Flat::new(self.ke), 1.0));
+		}
+
+		mat
+	}
+
+	/// Opacity texture (map_d) for cutting out the shape with `WithAlpha`,
+	/// e.g. for leaves. None if there is none.
+	pub fn opacity(&self, dir: &Path, textures: &mut HashMap<String, Arc<dyn Texture>>) -> Option<Arc<dyn Texture>> {
+		self.map_d
+			.as_ref()
+			.map(|fname| load_texture(dir, fname, textures, imageio::load_opacity, Color::WHITE))
+	}
+}
+
+// Load an image texture (once per file name), with OBJ texture coordinates
+// (v pointing up, so flipped with respect to image rows).
+fn load_texture<T, F>(dir: &Path, fname: &str, cache: &mut HashMap<String, Arc<dyn Texture>>, load: F, fallback: Color) -> Arc<dyn Texture>
+where
+	T: Texture,
+	F: Fn(&str) -> Result<T>,
+{
+	let path = dir.join(fname).to_string_lossy().into_owned();
+	if let Some(tex) = cache.get(&path) {
+		return tex.clone();
+	}
+	let tex: Arc<dyn Texture> = match load(&path) {
+		Ok(img) => Arc::new(Transformed::scale(img, (1.0, -1.0))),
+		Err(e) => {
+			println!("WARN: {}", e);
+			Arc::new(fallback)
+		}
+	};
+	cache.insert(path, tex.clone());
+	tex
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::test_rng;
+
+	#[test]
+	fn test_parse_mtl() {
+		let input = r"
+# Blender MTL File
+newmtl Glass
+Ns 900
+Ka 1.000000 1.000000 1.000000
+Kd 0.1 0.2 0.3
+Ks 0.5 0.5 0.5
+Ke 0 0 0
+Ni 1.45
+d 0.25
+illum 2
+
+newmtl Leaf
+Kd 0.2
+Tr 0.1
+map_Kd leaf.png
+map_d -clamp on leaf_alpha.png
+map_Bump -bm 0.5 leaf_height.png
+"
+		.as_bytes();
+		let lib = parse_mtl(input).unwrap();
+		assert_eq!(lib.len(), 2);
+
+		let glass = &lib["Glass"];
+		assert_eq!(glass.kd, Color::new(0.1, 0.2, 0.3));
+		assert_eq!(glass.ks, Color::new(0.5, 0.5, 0.5));
+		assert_eq!(glass.ns, 900.0);
+		assert_eq!(glass.ni, 1.45);
+		assert_eq!(glass.d, 0.25);
+		assert_eq!(glass.map_kd, None);
+
+		let leaf = &lib["Leaf"];
+		assert_eq!(leaf.kd, Color::new(0.2, 0.2, 0.2));
+		assert!((leaf.d - 0.9).abs() < 1e-6);
+		assert_eq!(leaf.map_kd.as_deref(), Some("leaf.png"));
+		assert_eq!(leaf.map_d.as_deref(), Some("leaf_alpha.png"));
+		assert_eq!(leaf.bump.as_deref(), Some("leaf_height.png"));
+		assert_eq!(leaf.bump_mult, 0.5);
+
+		assert!(parse_mtl("Kd 1 1 1\n".as_bytes()).is_err());
+		assert!(parse_mtl("newmtl a\nKd 1 1\n".as_bytes()).is_err());
+	}
+
+	// Background showing the ray direction as a color.
+	struct Dir;
+
+	fn dir_color(dir: Vector) -> Color {
+		let c = |x: f64| (0.5 + 0.5 * x) as f32;
+		Color::new(c(dir[0]), c(dir[1]), c(dir[2]))
+	}
+
+	impl Material for Dir {
+		fn shade(&self, _: &Scene, r: &Ray, _: &HitCoords, _: &mut Rng, _: u32) -> Color {
+			dir_color(r.dir)
+		}
+	}
+
+	// Color seen by a ray hitting plane z = 0 at 45 degrees, painted with `mtl`.
+	fn shade(mtl: &str, rng: &mut Rng) -> (Color, Vector) {
+		let lib = parse_mtl(mtl.as_bytes()).unwrap();
+		let mat = lib["m"].material(Path::new("."), &mut HashMap::new());
+		let scene = Scene {
+			background: DynMaterial::new(Dir),
+			max_recursion_depth: 3,
+			lights: vec![],
+			..crate::tests::default_scene()
+		};
+		let r = Ray::new(Point(-1., 0., 1.), Vector(1., 0., -1.).normalized());
+		let mut h = HitCoords::background(r.dir.into());
+		h.t = f64::sqrt(2.0);
+		h.geom_normalf = Vectorf::EZ;
+		h.shading_normalf = Vectorf::EZ;
+		(mat.shade(&scene, &r, &h, rng, 0), r.dir)
+	}
+
+	#[test]
+	fn test_material_ni() {
+		let mut trng = test_rng();
+		for i in 0..10 {
+			let mut rng = trng.for_pix((0, 0), i);
+			let straight = |(c, dir): (Color, Vector)| c == dir_color(dir);
+
+			// Ni 1: rays pass straight through.
+			assert!(straight(shade("newmtl m\nKd 0\nd 0\n", &mut rng)));
+			// Ni 1.5: rays are refracted or reflected.
+			assert!(!straight(shade("newmtl m\nKd 0\nd 0\nNi 1.5\n", &mut rng)));
+			// opaque: Ni is not used.
+			assert_eq!(shade("newmtl m\nKd 0\nNi 1.5\n", &mut rng).0, Color::BLACK);
+		}
+	}
+
+	#[test]
+	fn test_material_bump() {
+		// height increasing along u.
+		let dir = std::env::temp_dir().join("brilliance_test_material_bump");
+		std::fs::create_dir_all(&dir).unwrap();
+		let ramp = Image::<Color>::from_fn((64, 1), |x, _| {
+			let h = x as f32 / 63.0;
+			Color::new(h, h, h)
+		});
+		save(&ramp, dir.join("ramp.png").to_str().unwrap()).unwrap();
+
+		// Matte plane z = 0, lit from the side, seen from above.
+		let scene = Scene {
+			lights: vec![point_light(Point(5., 0., 5.), Color::WHITE)],
+			..crate::tests::default_scene()
+		};
+		let r = Ray::new(Point(0., 0., 1.), -Vector::EZ);
+		let mut h = HitCoords::background(r.dir.into());
+		h.t = 1.0;
+		h.geom_normalf = Vectorf::EZ;
+		h.shading_normalf = Vectorf::EZ;
+		h.tex_coords = Pointf(0.5, 0.5, 0.0);
+		h.dpdu = Vectorf::EX;
+		h.dpdv = Vectorf::EY;
+		let shade = |mtl: &str| {
+			let lib = parse_mtl(mtl.as_bytes()).unwrap();
+			let mat = lib["m"].material(&dir, &mut HashMap::new());
+			let mut trng = test_rng();
+			mat.shade(&scene, &r, &h, &mut trng.for_pix((0, 0), 0), 0).r()
+		};
+
+		let flat = shade("newmtl m\nKd 1\n");
+		// surface tilted away from the light.
+		assert!(shade("newmtl m\nKd 1\nbump -bm 0.5 ramp.png\n") < 0.9 * flat);
+		// negative multiplier: tilted towards the light.
+		assert!(shade("newmtl m\nKd 1\nbump -bm -0.5 ramp.png\n") > 1.1 * flat);
+		// missing file: no bumps.
+		assert_eq!(shade("newmtl m\nKd 1\nbump missing.png\n"), flat);
+	}
+}
@@ -2,71 +2,200 @@ use super::*;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
 use std::str;
+use std::sync::Arc;
 
-type Mesh2 = WithMaterial<QTree<Face>, DynMaterial>;
+// Sub-meshes with an opacity map (map_d) are cut out with `WithAlpha`,
+// the others (most) are not, so that they do not pay for the texture lookups.
+type Mesh2 = DynObj;
 
 /// Parse a Wavefront OBJ file. See
 /// https://en.wikipedia.org/wiki/Wavefront_.obj_file
+///
+/// Material libraries (`mtllib`) and their textures
+/// are loaded relative to the directory of the OBJ file.
 pub fn parse_file(fname: &str) -> Result<QTree<Mesh2>> {
-	parse(File::open(fname)?)
+	parse_file_with(fname, ObjOptions::default())
+}
+
+/// Like parse_file, but with non-default options.
+pub fn parse_file_with(fname: &str, opts: ObjOptions) -> Result<QTree<Mesh2>> {
+	let dir = Path::new(fname).parent().unwrap_or_else(|| Path::new(""));
+	Parser::new(opts, dir).parse(File::open(fname)?)
 }
 
 /// Like parse_file, but accepts a reader.
+/// Material libraries are loaded relative to the current working directory.
 pub fn parse<R: Read>(r: R) -> Result<QTree<Mesh2>> {
-	Parser::new().parse(r)
+	parse_with(r, ObjOptions::default())
+}
+
+/// Like parse, but with non-default options.
+pub fn parse_with<R: Read>(r: R, opts: ObjOptions) -> Result<QTree<Mesh2>> {
+	Parser::new(opts, Path::new("")).parse(r)
+}
+
+/// Like parse_file_with, but returns all faces as triangles, without materials.
+/// E.g. for use as a `MeshLight`.
+pub fn parse_file_faces(fname: &str, opts: ObjOptions) -> Result<Vec<Face>> {
+	let dir = Path::new(fname).parent().unwrap_or_else(|| Path::new(""));
+	let opts = ObjOptions {
+		quads: QuadMode::Split,
+		..opts
+	};
+	Parser::new(opts, dir).parse_faces(File::open(fname)?)
 }
 
-pub type MtlLib = HashMap<String, DynMaterial>;
+/// Options for parsing Wavefront OBJ files.
+#[derive(Clone, Debug, Default)]
+pub struct ObjOptions {
+	/// How to build the acceleration structure over the faces.
+	pub tree: BuildOptions,
+
+	/// How to render faces with 4 vertices.
+	pub quads: QuadMode,
+
+	/// Keep the coordinates as they are in the file.
+	/// By default, models are scaled to unit size,
+	/// and translated so that their bottom-center sits at the origin.
+	pub keep_units: bool,
+
+	/// The file's up axis. Models are converted to brilliance's Y-up.
+	pub up: UpAxis,
+
+	/// Reverse the vertex order of all faces (and the direction of specified normals).
+	/// For models that are rendered inside-out.
+	pub flip_winding: bool,
+
+	/// Smoothing angle for generated normals (when the file specifies none), in radians.
+	/// Faces meeting at a sharper angle get a hard edge. E.g.: `30.0 * DEG`.
+	/// By default, all faces sharing a vertex are smoothed.
+	pub crease_angle: Option<f64>,
+}
+
+/// Coordinate system convention for OBJ files.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum UpAxis {
+	/// Y points up (like brilliance, and most OBJ exporters).
+	#[default]
+	Y,
+	/// Z points up (e.g. CAD programs, Blender's native orientation).
+	/// Converted to Y-up: (x, y, z) -> (x, z, -y).
+	Z,
+}
+
+/// How to render faces with 4 vertices.
+/// (Faces with more vertices are always triangulated).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum QuadMode {
+	/// Split into two triangles.
+	#[default]
+	Split,
+	/// Keep as a single `Quad` (a bilinear patch if non-planar).
+	Keep,
+}
 
 struct Parser {
+	opts: ObjOptions,
 	curr_line: u32, // current line, for error messages
 	obj_start: u32,
 	curr_mtl: String,
 
-	//materials: MtlLib,
-	v_pos: Vec<Pointf>,   // vertex positions, parsed from "v ..."
-	t: Vec<TexCoords>,    // texture coordinates, parsed from "t ..."
-	n: Vec<Vectorf>,      // normals, parsed from "n ..."
-	f_def: Vec<FaceDef>,  // faces, parsed from "f ..."
-	calc_n: Vec<Vectorf>, // calculated normals, for when they're not specified
+	dir: PathBuf,        // directory for resolving mtllib and texture files
+	materials: MtlLib,   // parsed from "mtllib ..."
+	v_pos: Vec<Pointf>,  // vertex positions, parsed from "v ..."
+	t: Vec<TexCoords>,   // texture coordinates, parsed from "t ..."
+	n: Vec<Vectorf>,     // normals, parsed from "n ..."
+	f_def: Vec<FaceDef>, // faces, parsed from "f ..."
 	objects: Vec<ObjDef>,
 }
 
-// 3 or 4 elements.
-#[derive(Default)]
-struct FaceDef([FaceVert; 4]);
+// 3 or more vertices.
+struct FaceDef(Vec<FaceVert>);
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 struct FaceVert {
 	v: u32,
 	n: u32,
 	t: u32,
+	calc_n: Vectorf, // calculated normal, for when n is not specified
 }
 
 struct ObjDef {
-	//mtl: String,
+	mtl: String,
 	face_range: (u32, u32),
 }
 
 impl Parser {
-	fn new() -> Self {
+	fn new(opts: ObjOptions, dir: &Path) -> Self {
 		Self {
+			opts,
 			curr_line: 0,
 			obj_start: 0,
 			curr_mtl: String::new(),
 
-			//materials: MtlLib::new(),
+			dir: dir.to_owned(),
+			materials: MtlLib::new(),
 			v_pos: Vec::new(),
 			f_def: Vec::new(),
 			t: Vec::new(),
 			n: Vec::new(),
-			calc_n: Vec::new(),
 			objects: Vec::new(),
 		}
 	}
 
 	fn parse<R: Read>(mut self, r: R) -> Result<QTree<Mesh2>> {
+		self.read(r)?;
+
+		// materials are converted once, and shared between objects that use them.
+		// undefined materials (e.g. missing mtllib) fall back to the default gray.
+		let mut materials = HashMap::<String, (Arc<dyn Material>, Option<Arc<dyn Texture>>)>::new();
+		let mut textures = HashMap::new();
+
+		let mut meshes = Vec::<Mesh2>::new();
+		for obj in &self.objects {
+			let mut faces = Vec::<Polygon>::new();
+			for i in obj.face_range.0..obj.face_range.1 {
+				self.push_polygons(i, &mut faces)?;
+			}
+
+			let (mat, alpha) = materials
+				.entry(obj.mtl.clone())
+				.or_insert_with(|| {
+					let mtl = self.materials.get(&obj.mtl).cloned().unwrap_or_default();
+					(mtl.material(&self.dir, &mut textures), mtl.opacity(&self.dir, &mut textures))
+				})
+				.clone();
+
+			let mesh = QTree::with_options(faces, &self.opts.tree).paint(mat);
+			meshes.push(match alpha {
+				None => DynObj::new(mesh),
+				Some(alpha) => DynObj::new(mesh.with_alpha(alpha)),
+			})
+		}
+		Ok(QTree::with_options(meshes, &self.opts.tree))
+	}
+
+	// Like parse, but returns the faces of all objects as triangles.
+	fn parse_faces<R: Read>(mut self, r: R) -> Result<Vec<Face>> {
+		self.read(r)?;
+
+		let mut polygons = Vec::new();
+		for i in 0..self.f_def.len() {
+			self.push_polygons(i as u32, &mut polygons)?;
+		}
+		Ok(polygons
+			.into_iter()
+			.filter_map(|p| match p {
+				Polygon::Triangle(f) => Some(f),
+				Polygon::Quad(_) => None, // not produced with QuadMode::Split
+			})
+			.collect())
+	}
+
+	// Parse all lines, and calculate normals and scale as needed.
+	fn read<R: Read>(&mut self, r: R) -> Result<()> {
 		let reader = BufReader::new(r);
 		for line in reader.lines() {
 			// on parse error, prefix with current line number
@@ -78,32 +207,29 @@ impl Parser {
 		self.push_curr_obj();
 
 		self.calc_normals()?;
-		self.scale_to_unit_size();
-
-		let mut meshes = Vec::<Mesh2>::new();
-		for obj in &self.objects {
-			let mut faces = Vec::<Face>::new();
-			for i in obj.face_range.0..obj.face_range.1 {
-				faces.push(self.face(i)?);
-			}
-
-			meshes.push(WithMaterial::new(
-				QTree::new(faces),
-				//DynMaterial::new(ShadingNormal()),
-				DynMaterial::new(Matte::new(Color::new(0.7, 0.7, 0.7))),
-				//shiny(Color::new(0.7, 0.7, 0.7), 0.5),
-			))
+		if !self.opts.keep_units {
+			self.scale_to_unit_size();
 		}
-		Ok(QTree::new(meshes))
+		Ok(())
 	}
 
-	fn face(&self, i: u32) -> Result<Face> {
+	// Convert the i'th face definition into a triangle, quad,
+	// or triangles by ear clipping (depending on the number of vertices and options).
+	fn push_polygons(&self, i: u32, out: &mut Vec<Polygon>) -> Result<()> {
 		let fdef = &self.f_def[i as usize];
-		Ok(Face::new(
-			self.face_vertex(&fdef.0[0])?,
-			self.face_vertex(&fdef.0[1])?,
-			self.face_vertex(&fdef.0[2])?,
-		))
+		let v = fdef.0.iter().map(|vdef| self.face_vertex(vdef)).collect::<Result<Vec<Vertex>>>()?;
+
+		match (v.len(), self.opts.quads) {
+			(3, _) => out.push(Polygon::Triangle(Face::new(v[0].clone(), v[1].clone(), v[2].clone()))),
+			(4, QuadMode::Keep) => out.push(Polygon::Quad(Quad::new([v[0].clone(), v[1].clone(), v[2].clone(), v[3].clone()]))),
+			_ => {
+				let pos: Vec<Pointf> = v.iter().map(|v| v.pos).collect();
+				for [a, b, c] in triangulate(&pos) {
+					out.push(Polygon::Triangle(Face::new(v[a].clone(), v[b].clone(), v[c].clone())))
+				}
+			}
+		}
+		Ok(())
 	}
 
 	fn face_vertex(&self, vdef: &FaceVert) -> Result<Vertex> {
@@ -123,12 +249,20 @@ impl Parser {
 	// Return a nice error if the index is out-of-bounds.
 	fn normal(&self, fv: &FaceVert) -> Result<Vectorf> {
 		if fv.n == 0 {
-			Ok(self.calc_n[(fv.v - 1) as usize])
+			Ok(fv.calc_n)
 		} else {
 			Self::index_base1("normal", &self.n, fv.n)
 		}
 	}
 
+	// Convert from the file's coordinate system to brilliance's.
+	fn to_world(&self, v: Vectorf) -> Vectorf {
+		match self.opts.up {
+			UpAxis::Y => v,
+			UpAxis::Z => Vectorf(v[0], v[2], -v[1]),
+		}
+	}
+
 	// Get the i'th texture coordinates with base-1 indexing.
 	// Return a nice error if the index is out-of-bounds.
 	fn tex_coord(&self, i: u32) -> Result<TexCoords> {
@@ -152,6 +286,7 @@ impl Parser {
 			"vn" => self.parse_n(&args),
 			"f" => self.parse_f(&args),
 			"usemtl" => self.parse_usemtl(&args),
+			"mtllib" => self.parse_mtllib(&args),
 			_ => Ok(()), // ignore unknown commands
 		}
 	}
@@ -173,7 +308,8 @@ impl Parser {
 			return error(format!("vertex: need 3 coordinates, have: {:?}", args));
 		}
 
-		self.v_pos.push(Pointf::new(args[0].parse()?, args[1].parse()?, args[2].parse()?));
+		let v = Pointf::new(args[0].parse()?, args[1].parse()?, args[2].parse()?);
+		self.v_pos.push(self.to_world(v));
 
 		Ok(())
 	}
@@ -200,7 +336,8 @@ impl Parser {
 			return error(format!("vn: need 3 coordinates, have: {:?}", args));
 		}
 
-		self.n.push(Vectorf::new(args[0].parse()?, args[1].parse()?, args[2].parse()?));
+		let n = self.to_world(Vectorf::new(args[0].parse()?, args[1].parse()?, args[2].parse()?));
+		self.n.push(if self.opts.flip_winding { -n } else { n });
 
 		Ok(())
 	}
@@ -219,17 +356,15 @@ impl Parser {
 	//   f ...
 	#[must_use]
 	fn parse_f(&mut self, args: &[&str]) -> Result<()> {
-		if !(args.len() == 3 || args.len() == 4) {
-			println!("need 3 or 4 face indices, got {:?}", args);
-			return Ok(()); //TODO: subdivide?
-			   //return error(format!("need 3 or 4 face indices, got {:?}", args));
+		if args.len() < 3 {
+			return error(format!("face: need at least 3 vertices, got {:?}", args));
 		}
 
-		let mut fdef = FaceDef::default();
-		for (i, arg) in args.iter().enumerate() {
-			fdef.0[i] = Self::parse_f_arg(arg)?;
+		let mut fdef = args.iter().map(|arg| Self::parse_f_arg(arg)).collect::<Result<Vec<FaceVert>>>()?;
+		if self.opts.flip_winding {
+			fdef.reverse();
 		}
-		self.f_def.push(fdef);
+		self.f_def.push(FaceDef(fdef));
 
 		Ok(())
 	}
@@ -247,7 +382,12 @@ impl Parser {
 
 		let n: u32 = if words.len() > 2 { words[2].parse()? } else { 0 };
 
-		Ok(FaceVert { v, t, n })
+		Ok(FaceVert {
+			v,
+			t,
+			n,
+			calc_n: Vectorf::default(),
+		})
 	}
 
 	#[must_use]
@@ -265,6 +405,26 @@ impl Parser {
 		Ok(())
 	}
 
+	// Material library: one or more MTL files,
+	// relative to the OBJ file.
+	//   mtllib file1.mtl file2.mtl
+	// A missing library is reported, but not an error:
+	// the geometry is still useful with default materials.
+	fn parse_mtllib(&mut self, args: &[&str]) -> Result<()> {
+		if args.is_empty() {
+			return error("mtllib: need a file name".to_owned());
+		}
+
+		for fname in args {
+			let path = self.dir.join(fname);
+			match parse_mtl_file(&path) {
+				Ok(lib) => self.materials.extend(lib),
+				Err(e) => println!("WARN: mtllib {}: {}", path.to_string_lossy(), e),
+			}
+		}
+		Ok(())
+	}
+
 	fn push_curr_obj(&mut self) {
 		let start = self.obj_start;
 		let end = self.f_def.len() as u32;
@@ -273,7 +433,7 @@ impl Parser {
 		// So don't attempt to push an "empty" object.
 		if start != end {
 			self.objects.push(ObjDef {
-				//mtl: self.curr_mtl.clone(),
+				mtl: self.curr_mtl.clone(),
 				face_range: (start, end),
 			});
 		}
@@ -311,42 +471,45 @@ impl Parser {
 			return Ok(());
 		}
 
-		// allocate normals, all set to (0, 0, 0).
-		// will add gemotric normals of all adjacent faces.
-		self.calc_n = Vec::with_capacity(self.v_pos.len());
-		for _ in &self.v_pos {
-			self.calc_n.push(Vectorf::default());
+		// geometric normal of each face, weighted by area.
+		let face_n = self
+			.f_def
+			.iter()
+			.map(|fdef| {
+				let pos = fdef.0.iter().map(|vdef| self.vertex_pos(vdef.v)).collect::<Result<Vec<Pointf>>>()?;
+				Ok(polygon_normal(&pos))
+			})
+			.collect::<Result<Vec<Vectorf>>>()?;
+
+		// faces sharing each vertex.
+		let mut adj = vec![Vec::<u32>::new(); self.v_pos.len()];
+		for (i, fdef) in self.f_def.iter().enumerate() {
+			for vdef in &fdef.0 {
+				adj[(vdef.v - 1) as usize].push(i as u32);
+			}
 		}
 
-		// calculate normals: add face geometric normal
-		// to the normals belonging to each vertex the face shares.
-		for fdef in &self.f_def {
-			let vdef = fdef.vertices();
-			if fdef.is_quad() {
-				for i in 0..4 {
-					let a = self.vertex_pos(vdef[(i + 0) % 3].v)?;
-					let b = self.vertex_pos(vdef[(i + 1) % 3].v)?;
-					let c = self.vertex_pos(vdef[(i + 2) % 3].v)?;
-					let n = triangle_normal(a, b, c);
-					self.calc_n[(vdef[i].v - 1) as usize] += n;
-				}
-			} else {
-				for i in 0..3 {
-					let a = self.vertex_pos(vdef[i].v)?;
-					let b = self.vertex_pos(vdef[(i + 1) % 3].v)?;
-					let c = self.vertex_pos(vdef[(i + 2) % 3].v)?;
-					let n = triangle_normal(a, b, c);
-					self.calc_n[(vdef[i].v - 1) as usize] += n;
+		// the normal in each face corner is the sum of the geometric normals
+		// of all faces sharing the vertex. With a crease angle,
+		// only faces with a similar orientation contribute.
+		let min_cos = self.opts.crease_angle.map(|a| a.cos() as f32);
+		for (i, fdef) in self.f_def.iter_mut().enumerate() {
+			let n_i = face_n[i].normalized();
+			for vdef in &mut fdef.0 {
+				let mut n = Vectorf::default();
+				for &j in &adj[(vdef.v - 1) as usize] {
+					let smooth = match min_cos {
+						None => true,
+						Some(min_cos) => j as usize == i || face_n[j as usize].normalized().dot(n_i) >= min_cos,
+					};
+					if smooth {
+						n += face_n[j as usize];
+					}
 				}
+				vdef.calc_n = n.normalized();
 			}
 		}
 
-		// calc_n[i] now holds the sum of gemotric nomrmals
-		// of all faces sharing vertex[i]. it needs to be normalized.
-		for n in &mut self.calc_n {
-			n.normalize()
-		}
-
 		Ok(())
 	}
 
@@ -376,24 +539,10 @@ impl Parser {
 }
 
 impl FaceDef {
-	// returns 3 or 4 vertices, depending on how many were specified
-	fn vertices(&self) -> &[FaceVert] {
-		if self.is_quad() {
-			&self.0
-		} else {
-			&self.0[0..3]
-		}
-	}
-
-	// does this face have 4 vertices?
-	fn is_quad(&self) -> bool {
-		self.0[3].v != 0
-	}
-
 	// does this face have all its normals specified?
 	// (if not, they will need to be calculated)
 	fn has_normals(&self) -> bool {
-		for v in self.vertices() {
+		for v in &self.0 {
 			if v.n == 0 {
 				return false;
 			}
@@ -402,10 +551,6 @@ impl FaceDef {
 	}
 }
 
-fn triangle_normal(a: Pointf, b: Pointf, c: Pointf) -> Vectorf {
-	(b - a).cross(c - a)
-}
-
 #[cfg(test)]
 mod test {
 	use super::*;
@@ -428,4 +573,106 @@ f 1/1 2//1 3/1/1
 		let _ = parse(input).unwrap();
 		//println!("{:?}", &got);
 	}
+
+	// Quads and concave n-gons must be rendered without holes or extra area.
+	#[test]
+	fn test_polygons() {
+		// a concave "L" (n-gon) and a square (quad), in the XZ plane.
+		// after scaling to unit size, the L spans [-0.5, 0.5] in X and Z,
+		// with a notch in the (+X, +Z) quadrant.
+		let input = r"
+v 0 0 0
+v 2 0 0
+v 2 0 1
+v 1 0 1
+v 1 0 2
+v 0 0 2
+f 1 2 3 4 5 6
+f 3 4 5
+"
+		.as_bytes();
+		let quad = r"
+v 0 0 0
+v 2 0 0
+v 2 0 2
+v 0 0 2
+f 1 2 3 4
+"
+		.as_bytes();
+		let hits = |obj: &dyn Object, x: f64, z: f64| obj.occluded(&Ray::new(Point(x, 1.0, z), -Vector::EY), INF) != Occlusion::Clear;
+
+		for &quads in &[QuadMode::Split, QuadMode::Keep] {
+			let opts = ObjOptions {
+				quads,
+				..ObjOptions::default()
+			};
+			let l = parse_with(input, opts.clone()).unwrap();
+			assert!(hits(&l, -0.25, -0.25));
+			assert!(hits(&l, 0.25, -0.25));
+			assert!(hits(&l, -0.25, 0.25));
+			assert!(hits(&l, 0.1, 0.2)); // in the notch, inside triangle 3 4 5
+			assert!(!hits(&l, 0.4, 0.4)); // in the notch, outside triangle 3 4 5
+			assert!(!hits(&l, 0.6, 0.0));
+
+			let q = parse_with(quad, opts).unwrap();
+			for &(x, z) in &[(-0.45, -0.45), (0.45, -0.45), (0.45, 0.45), (-0.45, 0.45), (0.0, 0.0)] {
+				assert!(hits(&q, x, z));
+			}
+			assert!(!hits(&q, 0.55, 0.0));
+		}
+
+		assert!(parse("v 0 0 0\nv 1 0 0\nf 1 2\n".as_bytes()).is_err());
+	}
+
+	#[test]
+	fn test_options() {
+		// a floor (y = 0) and a wall (x = 0), sharing the edge from (0, 0, 0) to (0, 0, 2).
+		let input = r"
+v 0 0 0
+v 0 0 2
+v 2 0 2
+v 0 2 2
+v 0 2 0
+f 1 2 3
+f 1 5 4 2
+"
+		.as_bytes();
+		let intersect = |obj: &dyn Object, start: Point, dir: Vector| {
+			let mat = Flat::new(Color::BLACK);
+			let mut h = HitRecord::background(&mat, dir.into());
+			obj.intersect(&Ray::new(start, dir), &mut h);
+			h.coords
+		};
+
+		// original units, smoothed normals.
+		let opts = ObjOptions {
+			keep_units: true,
+			..ObjOptions::default()
+		};
+		let obj = parse_with(input, opts.clone()).unwrap();
+		assert_eq!(obj.bounds().max, Pointf(2., 2., 2.));
+		let h = intersect(&obj, Point(0.1, 1., 1.), -Vector::EY);
+		assert_eq!(h.t, 1.0);
+		assert!((h.geom_normal() - Vector::EY).len() < 1e-6);
+		assert!(h.shading_normal().dot(Vector::EX) > 0.1); // smoothed with the wall
+
+		// hard edge, normals pointing down.
+		let obj = parse_with(
+			input,
+			ObjOptions {
+				crease_angle: Some(30.0 * DEG),
+				flip_winding: true,
+				..opts.clone()
+			},
+		)
+		.unwrap();
+		let h = intersect(&obj, Point(0.1, 1., 1.), -Vector::EY);
+		assert!((h.geom_normal() + Vector::EY).len() < 1e-6);
+		assert!((h.shading_normal() + Vector::EY).len() < 1e-6);
+
+		// Z-up: the floor becomes a wall facing +Z.
+		let obj = parse_with(input, ObjOptions { up: UpAxis::Z, ..opts }).unwrap();
+		assert_eq!(obj.bounds().min, Pointf(0., 0., -2.));
+		assert_eq!(intersect(&obj, Point(0.5, 1.5, 1.), -Vector::EZ).t, 1.0);
+	}
 }
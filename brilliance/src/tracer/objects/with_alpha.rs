@@ -0,0 +1,148 @@
+use super::*;
+use crate::tracer::materials::internal::TINY;
+
+/// WithAlpha is a shape painted with a material, with parts cut out by an opacity texture.
+/// E.g. leaves or fences modeled as textured quads.
+///
+/// The opacity (average over the color channels of `alpha`) is looked up at the texture coordinates of each hit.
+/// Where it is 0, rays pass as if the shape was not there, for camera rays and shadow rays alike.
+/// Where it is between 0 and 1, rays hit with a probability equal to the opacity,
+/// decided by a hash of the hit position. This blends on average over the many rays per pixel.
+/// Camera and shadow rays only agree statistically: they rarely hit the very same point,
+/// but both see the same fraction cut out.
+///
+///     use brilliance::*;
+///     let leaf = Sphere::new(Point(0., 0., 0.), 1.0)
+///         .paint(Matte::new(Color::new(0.2, 0.6, 0.1)))
+///         .with_alpha(Color::new(0.5, 0.5, 0.5));
+pub struct WithAlpha<S: Shape, M: Material, T: Texture> {
+	shape: S,
+	material: M,
+	alpha: T,
+}
+
+impl<S: Shape, M: Material, T: Texture> WithAlpha<S, M, T> {
+	pub fn new(shape: S, material: M, alpha: T) -> Self {
+		Self { shape, material, alpha }
+	}
+
+	// Like Shape::intersect_coords, but skipping hits that are cut out.
+	fn intersect_opaque(&self, r: &Ray, h: &mut HitCoords) -> bool {
+		let mut start = 0.0; // continue behind cut-out hits
+		loop {
+			let sec = Ray::new(r.at(start), r.dir);
+			let mut c = HitCoords::background(r.dir.into());
+			c.t = h.t - start;
+			if !self.shape.intersect_coords(&sec, &mut c) {
+				return false;
+			}
+			if self.is_opaque(sec.at(c.t), c.tex_coords) {
+				c.t += start;
+				*h = c;
+				return true;
+			}
+			start += c.t + TINY;
+		}
+	}
+
+	fn is_opaque(&self, pos: Point, tex_coords: Pointf) -> bool {
+		let a = self.alpha.color_at(tex_coords).average();
+		if a >= 1.0 {
+			true
+		} else if a <= 0.0 {
+			false
+		} else {
+			hash(pos) < a
+		}
+	}
+}
+
+// Pseudo-random number between 0 and 1, fixed for each point in space.
+fn hash(p: Point) -> f32 {
+	// mix the coordinates' bits with the splitmix64 finalizer.
+	let mut x = p[0].to_bits() ^ p[1].to_bits().rotate_left(21) ^ p[2].to_bits().rotate_left(42);
+	x ^= x >> 30;
+	x = x.wrapping_mul(0xbf58476d1ce4e5b9);
+	x ^= x >> 27;
+	x = x.wrapping_mul(0x94d049bb133111eb);
+	x ^= x >> 31;
+	(x >> 40) as f32 / (1u64 << 24) as f32
+}
+
+impl<S: Shape, M: Material, T: Texture> Object for WithAlpha<S, M, T> {
+	#[inline]
+	fn intersect<'s>(&'s self, r: &Ray, h: &mut HitRecord<'s>) {
+		if self.intersect_opaque(r, &mut h.coords) {
+			h.material = &self.material
+		}
+	}
+
+	#[inline]
+	fn occluded(&self, r: &Ray, max_t: f64) -> Occlusion {
+		let mut h = HitCoords::background(r.dir.into());
+		h.t = max_t;
+		if self.intersect_opaque(r, &mut h) {
+			Occlusion::of(&self.material)
+		} else {
+			Occlusion::Clear
+		}
+	}
+}
+
+impl<S: Shape, M: Material, T: Texture> Bounded for WithAlpha<S, M, T> {
+	fn bounds(&self) -> BoundingBox {
+		self.shape.bounds()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	// Opacity 0, 0.3 and 1 in three vertical bands.
+	struct Bands;
+
+	impl Texture for Bands {
+		fn color_at(&self, p: Pointf) -> Color {
+			match p[0] {
+				u if u < 1.0 / 3.0 => Color::BLACK,
+				u if u < 2.0 / 3.0 => Color::new(0.3, 0.3, 0.3),
+				_ => Color::WHITE,
+			}
+		}
+	}
+
+	#[test]
+	fn test_cutout() {
+		// unit square at z=-1, in front of an opaque one at z=-2.
+		let v = |x, y, z| Vertex {
+			pos: Pointf(x, y, z),
+			attr: Attr::new(Vectorf::EZ, TexCoords::new(x, y)),
+		};
+		let quad = |z| Quad::new([v(0., 0., z), v(1., 0., z), v(1., 1., z), v(0., 1., z)]);
+		let front = quad(-1.).paint(Flat::new(Color::WHITE)).with_alpha(Bands);
+		let back = quad(-2.).paint(Flat::new(Color::WHITE));
+
+		let background = Flat::new(Color::BLACK);
+		let hit = |x: f64, y: f64| {
+			let r = Ray::new(Point(x, y, 0.), -Vector::EZ);
+			let mut h = HitRecord::background(&background, r.dir.into());
+			back.intersect(&r, &mut h);
+			front.intersect(&r, &mut h);
+			let t = h.t().round();
+			assert_eq!(front.occluded(&r, INF) == Occlusion::Opaque, t == 1.0);
+			t
+		};
+
+		// cut out: the ray continues to the surface behind.
+		assert_eq!(hit(0.1, 0.5), 2.0);
+		// opaque
+		assert_eq!(hit(0.9, 0.5), 1.0);
+
+		// partially transparent: a fraction of the rays hit.
+		let n = 10000;
+		let hits = (0..n).filter(|i| hit(0.4 + 0.2 * (*i as f64 + 0.5) / n as f64, 0.5) == 1.0).count();
+		let frac = hits as f64 / n as f64;
+		assert!((frac - 0.3).abs() < 0.02, "{}", frac);
+	}
+}
@@ -9,6 +9,11 @@ impl<S: Shape, M: Material> WithMaterial<S, M> {
 	pub fn new(shape: S, material: M) -> Self {
 		Self { shape, material }
 	}
+
+	/// Cut out parts of the shape where texture `alpha` is transparent, see `WithAlpha`.
+	pub fn with_alpha<T: Texture>(self, alpha: T) -> WithAlpha<S, M, T> {
+		WithAlpha::new(self.shape, self.material, alpha)
+	}
 }
 
 impl<S: Shape, M: Material> Object for WithMaterial<S, M> {
@@ -18,6 +23,15 @@ impl<S: Shape, M: Material> Object for WithMaterial<S, M> {
 			h.material = &self.material
 		}
 	}
+
+	#[inline]
+	fn occluded(&self, r: &Ray, max_t: f64) -> Occlusion {
+		if self.shape.intersects(r, max_t) {
+			Occlusion::of(&self.material)
+		} else {
+			Occlusion::Clear
+		}
+	}
 }
 
 impl<S: Shape, M: Material> Bounded for WithMaterial<S, M> {
@@ -6,6 +6,8 @@ use super::*;
 pub struct Ray {
 	pub start: Point,
 	pub dir: Vector,
+	/// Differentials, for camera rays. See `RayDiff`.
+	pub diff: Option<RayDiff>,
 }
 
 impl Ray {
@@ -15,7 +17,12 @@ impl Ray {
 	pub fn new(start: Point, dir: Vector) -> Self {
 		debug_assert!(start.is_finite());
 		debug_assert!(dir.is_normalized());
-		Ray { start, dir }
+		Ray { start, dir, diff: None }
+	}
+
+	/// Attach differentials, see `RayDiff`.
+	pub fn with_diff(self, diff: RayDiff) -> Self {
+		Self { diff: Some(diff), ..self }
 	}
 
 	/// Point at distance `t` (positive) from the start.
@@ -38,3 +45,103 @@ impl Ray {
 		self.start.is_finite() && self.dir.is_normalized()
 	}
 }
+
+/// Ray differentials: the change of a ray's start and direction
+/// when moving by one pixel along the image's x and y axes.
+/// Used to estimate the area covered by a pixel on a surface,
+/// and hence in its texture (see `Footprint`).
+///
+/// See H. Igehy, "Tracing Ray Differentials", SIGGRAPH (1999).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RayDiff {
+	pub dstart_dx: Vector,
+	pub dstart_dy: Vector,
+	pub ddir_dx: Vector,
+	pub ddir_dy: Vector,
+}
+
+impl RayDiff {
+	/// Differentials for a step `s` times as large.
+	pub fn scale(self, s: f64) -> Self {
+		Self {
+			dstart_dx: s * self.dstart_dx,
+			dstart_dy: s * self.dstart_dy,
+			ddir_dx: s * self.ddir_dx,
+			ddir_dy: s * self.ddir_dy,
+		}
+	}
+
+	/// Footprint in texture space of ray `r` (with these differentials) hitting at `h`.
+	/// Zero if the hit provides no tangents (`HitCoords::dpdu`, `dpdv`).
+	///
+	/// For the background (not hit), the texture coordinates are the ray direction,
+	/// so the footprint is the direction's differential.
+	pub fn footprint(&self, r: &Ray, h: &HitCoords) -> Footprint {
+		if h.t >= ENV_DIST {
+			// background or environment light: at infinity, only the direction matters.
+			return Footprint {
+				dx: self.ddir_dx.into(),
+				dy: self.ddir_dy.into(),
+			};
+		}
+
+		// position differentials: intersect the neighboring rays with the tangent plane at the hit.
+		let n = h.geom_normal();
+		let p = r.at(h.t);
+		let offset = |dstart: Vector, ddir: Vector| {
+			let (start, dir) = (r.start + dstart, r.dir + ddir);
+			let t = n.dot(p - start) / n.dot(dir);
+			start + t * dir - p
+		};
+		let (dpdx, dpdy) = (offset(self.dstart_dx, self.ddir_dx), offset(self.dstart_dy, self.ddir_dy));
+
+		// texture coordinate differentials: solve dp = du dpdu + dv dpdv (in the least-squares sense).
+		let (dpdu, dpdv): (Vector, Vector) = (h.dpdu.into(), h.dpdv.into());
+		let (a, b, c) = (dpdu.dot(dpdu), dpdu.dot(dpdv), dpdv.dot(dpdv));
+		let det = a * c - b * b;
+		let duv = |dp: Vector| {
+			let (e, f) = (dpdu.dot(dp), dpdv.dot(dp));
+			Vectorf(((c * e - b * f) / det) as f32, ((a * f - b * e) / det) as f32, 0.0)
+		};
+		let fp = Footprint {
+			dx: duv(dpdx),
+			dy: duv(dpdy),
+		};
+
+		// no tangents, or ray (nearly) parallel to the surface.
+		if fp.dx.is_finite() && fp.dy.is_finite() {
+			fp
+		} else {
+			Footprint::default()
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn test_footprint() {
+		// plane z=0, with texture coordinates (x/2, y/4).
+		let mut h = HitCoords::background(Vectorf::EZ);
+		h.t = 2.0;
+		h.dpdu = Vectorf(2.0, 0.0, 0.0);
+		h.dpdv = Vectorf(0.0, 4.0, 0.0);
+
+		// seen from straight above, at distance 2, with directions fanning out by 0.1 per pixel.
+		let r = Ray::new(Point(0., 0., 2.), -Vector::EZ).with_diff(RayDiff {
+			dstart_dx: Vector::ZERO,
+			dstart_dy: Vector::ZERO,
+			ddir_dx: Vector(0.1, 0.0, 0.0),
+			ddir_dy: Vector(0.0, 0.1, 0.0),
+		});
+		let fp = r.diff.unwrap().footprint(&r, &h);
+		assert!((fp.dx - Vectorf(0.1, 0.0, 0.0)).len() < 1e-6);
+		assert!((fp.dy - Vectorf(0.0, 0.05, 0.0)).len() < 1e-6);
+
+		// without tangents
+		h.dpdu = Vectorf::ZERO;
+		assert_eq!(r.diff.unwrap().footprint(&r, &h), Footprint::default());
+	}
+}
@@ -1,24 +1,74 @@
 use super::*;
 use std::clone::Clone;
 use std::cmp::min;
+use std::ops::Range;
 use std::thread::spawn;
 
+// tile size in pixels
+const T: u32 = 32;
+
+/// Render `scene.max_iter` samples per pixel and return the resulting image.
+/// See `Accumulator` for rendering progressively.
 pub fn render(scene: &Scene, v: &View, num_threads: u32) -> Image<Color> {
-	// tile size in pixels
-	const T: u32 = 32;
+	let mut acc = Accumulator::new(v.dimensions()).antialias(scene.max_iter != 1);
+	acc.add_samples(scene, v, scene.max_iter, num_threads);
+	acc.mean()
+}
 
-	let (w, h) = v.dimensions();
-	let (nx, ny) = split_tiles((w, h), T);
-
-	let (send_work, recv_work) = mpmc_channel::<Work>();
-	for ty in 0..ny {
-		for tx in 0..nx {
-			let work = Work {
-				min: (tx * T, ty * T),
-				max: (min(tx * T + T, w), min(ty * T + T, h)),
-			};
-			send_work.send(work).unwrap();
+/// A rectangular part of the image, rendered by a single worker thread.
+/// Retains the sum of all samples rendered so far, as well as the random number state
+/// so that more samples can be added later.
+pub(crate) struct Tile {
+	min: (u32, u32),
+	sum: Image<Color>,
+	rng: TileRng,
+}
+
+impl Tile {
+	/// Tiles covering an image of size `(w, h)`.
+	pub(crate) fn split((w, h): (u32, u32)) -> Vec<Tile> {
+		let (nx, ny) = split_tiles((w, h), T);
+		let mut tiles = Vec::with_capacity((nx * ny) as usize);
+		for ty in 0..ny {
+			for tx in 0..nx {
+				let lo = (tx * T, ty * T);
+				let hi = (min(lo.0 + T, w), min(lo.1 + T, h));
+				tiles.push(Tile {
+					min: lo,
+					sum: Image::new((hi.0 - lo.0, hi.1 - lo.1)),
+					rng: TileRng::new((T, T), ty * nx + tx),
+				});
+			}
 		}
+		tiles
+	}
+
+	/// Draw the mean over `num_samples` samples onto `img`.
+	pub(crate) fn draw_mean(&self, img: &mut Image<Color>, num_samples: u32) {
+		let (tw, th) = self.sum.dimensions();
+		for y in 0..th {
+			for x in 0..tw {
+				img.set((self.min.0 + x, self.min.1 + y), self.sum.at((x, y)) / (num_samples as f32));
+			}
+		}
+	}
+}
+
+/// Add samples with indices `iters` to each pixel of each tile.
+/// Textures are filtered over `spacing` times the pixel size (see `sample_spacing`).
+/// Tiles are distributed over `num_threads` worker threads, and returned in arbitrary order.
+pub(crate) fn render_tiles(
+	scene: &Scene,
+	v: &View,
+	tiles: Vec<Tile>,
+	iters: Range<u32>,
+	antialias: bool,
+	spacing: f64,
+	num_threads: u32,
+) -> Vec<Tile> {
+	let (send_work, recv_work) = mpmc_channel::<Tile>();
+	for tile in tiles {
+		send_work.send(tile).unwrap();
 	}
 	drop(send_work); // let workers know no more work is coming
 
@@ -30,29 +80,26 @@ pub fn render(scene: &Scene, v: &View, num_threads: u32) -> Image<Color> {
 	let scene = unsafe { static_cast(scene) };
 
 	let mut handles = Vec::new();
-	for i in 0..num_threads {
+	for _ in 0..num_threads {
 		let scene = scene.clone();
 		let recv_work = recv_work.clone();
 		let v = v.clone();
+		let iters = iters.clone();
 		handles.push(spawn(move || {
-			let mut tiles = Vec::new();
-			for work in recv_work {
-				let rng = TileRng::new((T, T), i);
-				let img = render_tile(rng, scene, &v, work.min, work.max);
-				tiles.push(Done { min: work.min, img });
+			let mut done = Vec::new();
+			for mut tile in recv_work {
+				render_tile(&mut tile, scene, &v, iters.clone(), antialias, spacing);
+				done.push(tile);
 			}
-			tiles
+			done
 		}));
 	}
 
-	let mut img = Image::new(v.dimensions());
+	let mut tiles = Vec::new();
 	for h in handles {
-		for done in h.join().unwrap() {
-			img.draw(done.min, &done.img);
-		}
+		tiles.extend(h.join().unwrap());
 	}
-
-	img
+	tiles
 }
 
 // number of `tile` by `tile` pixel tiles needed to cover `w` by `h` image.
@@ -71,43 +118,46 @@ unsafe fn static_cast<T>(t: &T) -> &'static T {
 	&*t
 }
 
-fn render_tile(mut rng: TileRng, s: &Scene, v: &View, min: (u32, u32), max: (u32, u32)) -> Image<Color> {
+fn render_tile(tile: &mut Tile, s: &Scene, v: &View, iters: Range<u32>, antialias: bool, spacing: f64) {
 	let (w, h) = v.dimensions();
-	let (tw, th) = (max.0 - min.0, max.1 - min.1);
-
-	Image::from_fn((tw, th), |x, y| {
-		let mut acc = Color::BLACK;
-		for iter in 0..s.max_iter {
-			let mut rng = rng.for_pix((x, y), iter);
-			let aa = aa(&rng, s);
-			let uv = index_to_cam((w, h), ((x + min.0) as f64 + aa.0, (y + min.1) as f64 + aa.1));
-
-			acc += s.image_fn(&mut rng, &v.camera, uv);
-		}
-		if !acc.is_finite() {
-			println!("WARN: got NaN color");
+	let footprint = spacing / w as f64;
+	let (tw, th) = tile.sum.dimensions();
+	let min = tile.min;
+
+	for y in 0..th {
+		for x in 0..tw {
+			let mut acc = Color::BLACK;
+			for iter in iters.clone() {
+				let mut rng = tile.rng.for_pix((x, y), iter);
+				let aa = aa(&rng, antialias);
+				let uv = index_to_cam((w, h), ((x + min.0) as f64 + aa.0, (y + min.1) as f64 + aa.1));
+
+				acc += s.image_fn(&mut rng, &v.camera, uv, footprint);
+			}
+			if !acc.is_finite() {
+				println!("WARN: got NaN color");
+			}
+			tile.sum[y as usize][x as usize] += acc;
 		}
-		acc / (s.max_iter as f32)
-	})
+	}
 }
 
-fn aa(rng: &Rng, s: &Scene) -> (f64, f64) {
-	if s.max_iter == 1 {
-		(0.0, 0.0)
+/// Distance between samples, relative to the pixel size, when `samples` jittered samples
+/// are averaged per pixel. Textures only need to be filtered over this distance,
+/// the averaging takes care of the rest.
+pub(crate) fn sample_spacing(samples: u32, antialias: bool) -> f64 {
+	if antialias {
+		f64::max(0.125, 1.0 / f64::sqrt(u32::max(1, samples) as f64))
 	} else {
-		let (aa0, aa1) = rng.quasi_random2();
-		(aa0 as f64 - 0.5, aa1 as f64 - 0.5)
+		1.0 // all samples at the pixel center
 	}
 }
 
-#[derive(Debug)]
-struct Work {
-	min: (u32, u32),
-	max: (u32, u32),
-}
-
-#[derive(Debug)]
-struct Done {
-	min: (u32, u32),
-	img: Image<Color>,
+fn aa(rng: &Rng, antialias: bool) -> (f64, f64) {
+	if antialias {
+		let (aa0, aa1) = rng.quasi_random2();
+		(aa0 as f64 - 0.5, aa1 as f64 - 0.5)
+	} else {
+		(0.0, 0.0)
+	}
 }
@@ -6,7 +6,7 @@ use rand_xoshiro::rand_core::SeedableRng;
 use rand_xoshiro::Xoshiro256StarStar;
 
 pub struct TileRng {
-	shifts: Image<(f32, f32)>, // TODO: no need to store this unless iteratively refining.
+	shifts: Image<(f32, f32)>, // stored so that a tile can be iteratively refined.
 	rng: Xoshiro256StarStar,
 }
 
@@ -20,11 +20,7 @@ impl TileRng {
 	}
 
 	fn xoshiro(n: u32) -> Xoshiro256StarStar {
-		let mut rng = Xoshiro256StarStar::seed_from_u64(0);
-		for _ in 0..n {
-			rng.jump();
-		}
-		rng
+		Xoshiro256StarStar::seed_from_u64(n as u64)
 	}
 
 	pub fn for_pix(&mut self, pix: (u32, u32), iter: u32) -> Rng {
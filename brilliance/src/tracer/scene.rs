@@ -1,18 +1,31 @@
 use super::*;
+use crate::tracer::materials::internal::TINY;
+use std::sync::Arc;
 
 pub struct Scene {
-	pub objects: Vec<DynObj>, // TODO: QTree<DynObj>
+	/// Top-level objects, typically constructed with `QTree::new(vec![...])`.
+	/// Objects may themselves be trees (e.g. meshes), making this a two-level hierarchy.
+	pub objects: QTree<DynObj>,
 	pub lights: Vec<DynLight>,
 	pub ambient: Color,
 	pub background: DynMaterial,
+	/// Medium filling the space outside of `Volume`s (e.g. fog), or None for vacuum.
+	pub medium: Option<Arc<dyn Medium>>,
 	pub max_recursion_depth: u32,
 	pub max_iter: u32,
 }
 
 impl Scene {
-	pub fn image_fn(&self, rng: &mut Rng, c: &Camera, uv: (f64, f64)) -> Color {
-		let ray = &c.ray_from(rng, uv);
-		self.lightfield(ray, rng, 0)
+	/// Color seen by camera `c` at camera coordinates `uv`,
+	/// with textures filtered over a square `footprint` wide (in camera coordinates, see `index_to_cam`):
+	/// a whole pixel for a single sample per pixel, less when many jittered samples are averaged.
+	pub fn image_fn(&self, rng: &mut Rng, c: &Camera, uv: (f64, f64), footprint: f64) -> Color {
+		let ray = c.ray_from(rng, uv);
+		let ray = match ray.diff {
+			Some(d) => ray.clone().with_diff(d.scale(footprint)),
+			None => ray,
+		};
+		self.lightfield(&ray, rng, 0)
 	}
 
 	pub fn lights(&self) -> &[DynLight] {
@@ -20,6 +33,23 @@ impl Scene {
 	}
 
 	pub fn lightfield(&self, r: &Ray, rng: &mut Rng, depth: u32) -> Color {
+		self.trace(r, None, rng, depth, true)
+	}
+
+	/// Like `lightfield`, but for the random (indirect) rays of materials
+	/// that also sample the lights directly (like `Matte`).
+	/// Rays hitting a light see black, so that its light is not counted twice.
+	pub fn lightfield_indirect(&self, r: &Ray, rng: &mut Rng, depth: u32) -> Color {
+		self.trace(r, None, rng, depth, false)
+	}
+
+	/// Like `lightfield`, for a ray starting inside `medium`
+	/// (e.g. just inside a `Volume`). None means the scene's own medium.
+	pub fn lightfield_in(&self, r: &Ray, medium: Option<&dyn Medium>, rng: &mut Rng, depth: u32) -> Color {
+		self.trace(r, medium, rng, depth, true)
+	}
+
+	fn trace(&self, r: &Ray, medium: Option<&dyn Medium>, rng: &mut Rng, depth: u32, lights_visible: bool) -> Color {
 		debug_assert!(r.is_valid());
 
 		if depth > self.max_recursion_depth {
@@ -28,44 +58,190 @@ impl Scene {
 
 		let mut h = HitRecord::background(&self.background, r.dir.into());
 
-		for o in &self.objects {
-			o.intersect(r, &mut h);
-			debug_assert!(h.is_valid());
-		}
+		self.objects.intersect(r, &mut h);
+		debug_assert!(h.is_valid());
 
+		let t = h.t();
+		// Lights are tested one by one rather than through a QTree: scenes have few of them,
+		// most are not hit at all (points, directions), and `lights()` must stay a plain list for sampling.
 		for o in &self.lights {
 			o.intersect(r, &mut h);
 			debug_assert!(h.is_valid());
 		}
+		let hit_light = h.t() < t;
+
+		// The ray may scatter in a medium before reaching the surface.
+		let mut weight = Color::WHITE;
+		if let Some(m) = medium.or(self.medium.as_deref()) {
+			match m.sample(r, h.t(), rng) {
+				MediumSample::Scatter { t, weight } => return weight * self.scatter(m, &Ray::new(r.at(t), r.dir), rng, depth),
+				MediumSample::Pass { weight: w } => weight = w,
+			}
+			if weight == Color::BLACK {
+				return weight;
+			}
+		}
+
+		if hit_light && !lights_visible {
+			return Color::BLACK;
+		}
 
 		// Object.intersect does not need to normalize normals to unit length,
 		// so do it here.
 		h.coords.geom_normalf.normalize();
 		h.coords.shading_normalf.normalize();
 
-		h.material.shade(self, r, &h.coords, rng, depth)
+		if let Some(d) = &r.diff {
+			h.coords.footprint = d.footprint(r, &h.coords);
+		}
+
+		// Volume boundary: continue straight on, into or out of the medium.
+		if let Some(inside) = h.material.medium() {
+			let entering = h.coords.geom_normal().dot(r.dir) < 0.0;
+			let sec = Ray::new(r.at(h.t() + 2.0 * TINY), r.dir);
+			let medium = if entering { Some(inside) } else { None };
+			return weight * self.trace(&sec, medium, rng, depth, lights_visible); // no need to increase depth, ray direction unchanged
+		}
+
+		weight * h.material.shade(self, r, &h.coords, rng, depth)
 	}
 
-	pub fn lightfield_indirect(&self, r: &Ray, rng: &mut Rng, depth: u32) -> Color {
-		self.lightfield(r, rng, depth) // TODO
+	// Light scattered by medium `m` at the start of ray `r`, into the direction opposite to `r`.
+	// Like `Matte`: light sources are sampled directly, and one random ray is added
+	// for the indirect illumination.
+	fn scatter(&self, m: &dyn Medium, r: &Ray, rng: &mut Rng, depth: u32) -> Color {
+		let p = r.start;
+		let phase = m.phase();
+		let mut acc = Color::BLACK;
+
+		// direct
+		for l in self.lights() {
+			let (lpos, intens) = l.sample(rng, p);
+			if intens == (Color::BLACK) {
+				continue;
+			}
+			let ldelta = lpos - p;
+			let ldir = ldelta.normalized();
+			// phase function, relative to Matte (times pi).
+			let f = PI * phase.eval(r.dir.dot(ldir));
+			let sec = Ray::new(p, ldir);
+			acc += self.occlude_in(&sec, ldelta.len(), intens, Some(m)) * (f as f32);
+		}
+
+		// indirect
+		let dir = phase.sample(r.dir, (rng.random() as f64, rng.random() as f64));
+		acc + self.trace(&Ray::new(p, dir), Some(m), rng, depth + 1, false)
 	}
 
+	/// Attenuate color `orig` along ray segment `r` of length `len`
+	/// (e.g., from a surface point towards a light source).
+	///
+	/// All objects between the ray start and `len` attenuate the color
+	/// (via `Material::occlude`), in front-to-back order,
+	/// as do the media in between (the scene's medium, and `Volume`s).
+	/// Lights do not occlude.
 	pub fn occlude(&self, r: &Ray, len: f64, orig: Color) -> Color {
+		self.occlude_in(r, len, orig, None)
+	}
+
+	// Like occlude, for a ray starting inside `medium` (None: the scene's medium).
+	fn occlude_in(&self, r: &Ray, len: f64, orig: Color, medium: Option<&dyn Medium>) -> Color {
+		let mut medium = medium.or(self.medium.as_deref());
+
+		// fast paths: nothing in the way, or anything opaque in the way.
+		// Otherwise, visit the (translucent) occluders in order.
+		match self.objects.occluded(r, len) {
+			Occlusion::Clear => {
+				return match medium {
+					None => orig,
+					Some(m) => orig * m.transmittance(r, 0.0, len),
+				}
+			}
+			Occlusion::Opaque => return Color::BLACK,
+			Occlusion::Partial => (),
+		}
+
 		let mut occluded = orig;
 
 		let background = Flat::new(Color::BLACK);
-		let mut h = HitRecord::background(&background, r.dir.into());
-		for o in &self.objects {
-			o.intersect(r, &mut h);
-			debug_assert!(h.is_valid());
-			if h.coords.t < len {
-				occluded = h.material.occlude(occluded, r.at(h.t()));
-				if occluded == Color::BLACK {
-					return occluded;
+		let mut r = r.clone();
+		let mut len = len;
+		loop {
+			let mut h = HitRecord::background(&background, r.dir.into());
+			h.coords.t = len;
+			self.objects.intersect(&r, &mut h);
+			if let Some(m) = medium {
+				occluded = occluded * m.transmittance(&r, 0.0, h.t());
+			}
+			if h.t() >= len {
+				return occluded; // nothing (more) in the way
+			}
+
+			match h.material.medium() {
+				// volume boundary: entering or leaving its medium.
+				Some(inside) => {
+					let entering = h.coords.geom_normal().dot(r.dir) < 0.0;
+					medium = if entering { Some(inside) } else { self.medium.as_deref() };
 				}
+				None => occluded = h.material.occlude(occluded, r.at(h.t())),
+			}
+			if occluded == Color::BLACK {
+				return occluded;
 			}
+
+			// continue right behind the occluder.
+			let step = h.t() + TINY;
+			r.start = r.at(step);
+			len -= step;
 		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::api::*;
+	use crate::tests::default_scene;
+
+	#[test]
+	fn occlude() {
+		let half = Color::new(0.5, 0.5, 0.5);
+		let glass = || DynMaterial::new(Translucent::new(half, Color::BLACK));
+		let scene = Scene {
+			objects: QTree::new(vec![
+				DynObj::new(Sphere::new(Point(0., 0., -2.), 1.0).paint(glass())),
+				DynObj::new(Sphere::new(Point(0., 0., -4.), 1.0).paint(glass())),
+				DynObj::new(Sphere::new(Point(0., 0., -6.), 1.0).paint(flat(WHITE))),
+			]),
+			..default_scene()
+		};
+		let r = Ray::new(Point(0., 0., 0.), -Vector::EZ);
+
+		// each glass sphere is crossed twice (entry and exit).
+		assert_eq!(scene.occlude(&r, 1.0, WHITE), WHITE);
+		assert_eq!(scene.occlude(&r, 2.0, WHITE), half);
+		assert_eq!(scene.occlude(&r, 3.0, WHITE), half * half);
+		assert_eq!(scene.occlude(&r, 5.0, WHITE), half * half * half * half);
+		assert_eq!(scene.occlude(&r, 7.0, WHITE), BLACK);
+	}
+
+	#[test]
+	fn occluded() {
+		let glass = DynMaterial::new(Translucent::new(Color::new(0.5, 0.5, 0.5), Color::BLACK));
+		let objects = QTree::new(vec![
+			DynObj::new(Sphere::new(Point(0., 0., -2.), 1.0).paint(glass)),
+			DynObj::new(Sphere::new(Point(0., 0., -6.), 1.0).paint(matte(WHITE))),
+		]);
+		let r = Ray::new(Point(0., 0., 0.), -Vector::EZ);
+		assert_eq!(objects.occluded(&r, 0.5), Occlusion::Clear);
+		assert_eq!(objects.occluded(&r, 2.0), Occlusion::Partial);
+		assert_eq!(objects.occluded(&r, 6.0), Occlusion::Opaque);
+	}
 
-		occluded
+	#[test]
+	fn empty() {
+		let scene = default_scene();
+		let r = Ray::new(Point(0., 0., 0.), -Vector::EZ);
+		assert_eq!(scene.occlude(&r, INF, WHITE), WHITE);
 	}
 }
@@ -4,6 +4,21 @@ use std::sync::Arc;
 pub trait Texture: Send + Sync + 'static {
 	// + Clone?
 	fn color_at(&self, p: Pointf) -> Color;
+
+	/// Average color over the footprint of a pixel around `p`, to avoid aliasing.
+	/// By default, the footprint is ignored. See `MipMap`.
+	fn color_filtered(&self, p: Pointf, _fp: &Footprint) -> Color {
+		self.color_at(p)
+	}
+}
+
+/// Footprint of a pixel in texture space:
+/// the change of the texture coordinates when moving by one pixel along the image's x and y axes.
+/// Zero if unknown. See `RayDiff`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Footprint {
+	pub dx: Vectorf,
+	pub dy: Vectorf,
 }
 
 impl Texture for Color {
@@ -31,6 +46,11 @@ impl<T: Texture> Texture for Arc<T> {
 		let t: &T = self.borrow();
 		t.color_at(p)
 	}
+
+	fn color_filtered(&self, p: Pointf, fp: &Footprint) -> Color {
+		let t: &T = self.borrow();
+		t.color_filtered(p, fp)
+	}
 }
 
 impl Texture for Arc<dyn Texture> {
@@ -38,9 +58,14 @@ impl Texture for Arc<dyn Texture> {
 		let t: &dyn Texture = self.borrow();
 		t.color_at(p)
 	}
+
+	fn color_filtered(&self, p: Pointf, fp: &Footprint) -> Color {
+		let t: &dyn Texture = self.borrow();
+		t.color_filtered(p, fp)
+	}
 }
 
-fn bilinear<C: Into<Color> + Copy + Default>(img: &Image<C>, (u, v): (f32, f32)) -> Color {
+pub(crate) fn bilinear<C: Into<Color> + Copy + Default>(img: &Image<C>, (u, v): (f32, f32)) -> Color {
 	let (w, h) = img.dimensions();
 
 	let X = warp(u) * (w - 1) as f32;
@@ -124,4 +149,14 @@ impl<T: Texture> Texture for Transformed<T> {
 		let pan = Pointf(self.pan.0, self.pan.1, 0.0);
 		self.inner.color_at(p * scale - pan)
 	}
+
+	fn color_filtered(&self, p: Pointf, fp: &Footprint) -> Color {
+		let scale = Pointf(self.scale.0, self.scale.1, 1.0);
+		let pan = Pointf(self.pan.0, self.pan.1, 0.0);
+		let fp = Footprint {
+			dx: fp.dx * scale,
+			dy: fp.dy * scale,
+		};
+		self.inner.color_filtered(p * scale - pan, &fp)
+	}
 }
@@ -0,0 +1,90 @@
+//! Physical units for light sources.
+//!
+//! Internally, brightness is relative to display white:
+//! a white `Matte` surface receiving irradiance 1 renders as pixel value 1.
+//! The functions below take that unit to be 1 lux (lumen per square meter),
+//! with lengths in meters, and convert to the quantity each light expects:
+//!
+//!  * `PointLight`, `sphere_light`, `SpotLight`: power, e.g. `lumens`, `watts`, or `candela` (for the beam).
+//!  * `DirectionalLight`: irradiance, e.g. `lux`.
+//!  * `RectLight`, `DiskLight`, `SphereLight::new`, `Emissive`: surface brightness, e.g. `nits`.
+//!
+//! The color only sets the hue: it is scaled to the requested luminous quantity.
+//!
+//! Real-world light levels span many orders of magnitude
+//! (about 300 lux indoors, 100 000 lux in direct sunlight).
+//! `Color::ev` serves as the camera's exposure: for a scene where typical surfaces receive
+//! E lux, `.ev(-log2(E))` renders them near white. E.g. about -8 indoors, -17 in sunlight.
+//!
+//!     use brilliance::*;
+//!     let bulb = point_light(Point(0., 2.5, 0.), lumens(Color::new(1.0, 0.9, 0.7), 800.0).ev(-6.0));
+//!     let sun = DirectionalLight::new(Vector(1., 2., 1.), lux(WHITE, 100_000.0).ev(-17.0));
+use super::*;
+
+/// Luminous efficacy of monochromatic 555 nm light, in lumen per watt
+/// (the maximum possible, by definition of the candela).
+pub const LM_PER_WATT: f64 = 683.0;
+
+/// Luminous flux (total visible power, in all directions),
+/// as printed on light bulb packaging. E.g. 800 lm for a common household bulb.
+pub fn lumens(color: Color, lm: f64) -> Color {
+	let lum = color.luminance();
+	if lum <= 0.0 {
+		return Color::BLACK;
+	}
+	color * (lm / lum as f64) as f32
+}
+
+/// Radiant flux: the power emitted as light, not the electrical power drawn.
+/// Converted at the maximum luminous efficacy (`LM_PER_WATT`).
+/// Real lamps convert only a fraction of their electrical power to light:
+/// a 60 W incandescent bulb emits about 800 lm, better given with `lumens`.
+pub fn watts(color: Color, w: f64) -> Color {
+	lumens(color, w * LM_PER_WATT)
+}
+
+/// Luminous intensity (flux per steradian), of a point light or a spot light's beam.
+/// E.g. 100 cd for a 800 lm bulb, 1 cd for a candle.
+pub fn candela(color: Color, cd: f64) -> Color {
+	lumens(color, 4.0 * PI * cd)
+}
+
+/// Illuminance (flux received per square meter), for directional lights.
+/// E.g. 100 000 lux for direct sunlight, 10 000 lux for overcast daylight.
+pub fn lux(color: Color, lx: f64) -> Color {
+	lumens(color, lx)
+}
+
+/// Luminance (brightness of a surface, in candela per square meter), for area lights.
+/// E.g. 200 nits for a computer screen, 10 000 nits for a frosted light panel.
+pub fn nits(color: Color, nt: f64) -> Color {
+	// area lights take the flux leaving the surface per square meter (exitance),
+	// pi times the luminance for surfaces that look equally bright from all sides.
+	lumens(color, PI * nt)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::tests::test_rng;
+
+	#[test]
+	fn test_units() {
+		let close = |a: Color, b: f64| (a.luminance() as f64 - b).abs() < 1e-3 * b;
+
+		// color only sets the hue.
+		assert!(close(lumens(Color::new(1.0, 0.5, 0.2), 800.0), 800.0));
+		assert!(close(lumens(Color::RED, 800.0), 800.0));
+		assert_eq!(lumens(Color::WHITE, 2.0), Color::new(2.0, 2.0, 2.0));
+
+		// 100 cd point light: 100 lux at 1 m.
+		let bulb = PointLight::new(Point(0., 0., 0.), candela(Color::WHITE, 100.0));
+		let mut trng = test_rng();
+		let mut rng = trng.for_pix((0, 0), 0);
+		assert!(close(bulb.sample(&mut rng, Point(0., -1., 0.)).1, 100.0));
+
+		// 1000 nits panel: as bright as a white surface under pi * 1000 lux.
+		assert!(close(nits(Color::WHITE, 1000.0), PI * 1000.0));
+		assert!(close(watts(Color::WHITE, 1.0), LM_PER_WATT));
+	}
+}
@@ -3,7 +3,7 @@ use super::*;
 pub fn sphere_map(p: Pointf) -> Pointf {
 	let (x, y, z) = (p[0] as f32, p[1] as f32, p[2] as f32);
 	let u = 0.5 + (f32::atan2(z, x)) / (2.0 * PI as f32);
-	let v = 0.5 - f32::asin(y) / (PI as f32); // sphere
+	let v = 0.5 - f32::asin(y.clamp(-1.0, 1.0)) / (PI as f32); // sphere
 	Pointf::new(u, v, 0.0)
 }
 
@@ -34,4 +34,16 @@ impl<T: Texture> Texture for UVMapped<T> {
 	fn color_at(&self, p: Pointf) -> Color {
 		self.tex.color_at((self.uvmap)(p))
 	}
+
+	fn color_filtered(&self, p: Pointf, fp: &Footprint) -> Color {
+		// map the footprint by finite differences.
+		// u wraps around, so the short way around is taken.
+		let uv = (self.uvmap)(p);
+		let d = |dp: Vectorf| {
+			let d = (self.uvmap)(p + dp) - uv;
+			Vectorf(d[0] - d[0].round(), d[1], d[2])
+		};
+		let fp = Footprint { dx: d(fp.dx), dy: d(fp.dy) };
+		self.tex.color_filtered(uv, &fp)
+	}
 }
@@ -135,15 +135,17 @@ fn main() -> Result<()> {
 		//let sun = DynLight::new(PointLight::new(Point(0.0, 0.1, 0.0), Color::new(1.0, 0.95, 0.70).ev(5.5)));
 		let sun_pos = Point(0., 0., 0.);
 		let sun_color = Color::new(1.0, 0.95, 0.70);
-		let sun = sphere_light(sun_pos, 2.0 * body_propts[0].radius_m / AU, sun_color.ev(args.sun_ev));
+		// power such that sun_ev is the exposure at 1 AU (light falls off with the inverse square distance).
+		let sun_power = sun_color.ev(args.sun_ev) * (4.0 * PI) as f32;
+		let sun = sphere_light(sun_pos, 2.0 * body_propts[0].radius_m / AU, sun_power);
 
 		let s = Scene {
 			max_recursion_depth: args.recursion,
 			max_iter: args.samples,
 			ambient: WHITE * args.ambient_light,
 			background: flat(UVMapped::sphere(backdrop.clone())),
-			//objects: vec![DynObj::new(QTree::new(objects))], // TODO: occlusion bug
-			objects,
+			medium: None,
+			objects: QTree::new(objects),
 			lights: vec![sun], // TODO
 		};
 
@@ -225,8 +227,7 @@ fn meteorite(p: &Planet, pos: Point, args: &Cli) -> DynObj {
 	DynObj::new(TransformedObj::new(
 		//Sphere::new(Point(0.0, 0.0, 0.0), 1.0).paint(matte(WHITE)),
 		parse_file("meteorite.obj").expect("load meteorite.obj"),
-		p.radius_m / AU,
-		pos,
+		Transform::scale(Point(0.0, 0.0, 0.0), p.radius_m / AU).before(&Transform::translate(pos)),
 	))
 }
 
@@ -244,7 +245,7 @@ fn tex(fname: &str, fallback: Color) -> Arc<dyn Texture> {
 	match load(fname) {
 		Ok(tex) => {
 			println!("[V] loaded {}", fname);
-			Arc::new(tex)
+			Arc::new(MipMap::new(tex).with_anisotropy(8))
 		}
 		Err(e) => {
 			eprintln!("[!] {}", e.to_string());